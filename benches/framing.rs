@@ -0,0 +1,144 @@
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use my_tcp_sockets::{
+    socket_reader::{ReadBuffer, SocketReader, SocketReaderInMem},
+    TcpSocketSerializer,
+};
+use yb_tcp_contracts::{
+    framing::{build_frame, locate_frame},
+    tcp_serializer::YourBourseFixTcpSerializer,
+    FixMessage, FixMessagePrinter, YbFixSettings, YbTcpSate, DEFAULT_MAX_MESSAGE_SIZE,
+    DEFAULT_READ_BUFFER_SIZE,
+};
+
+const MESSAGES_COUNT: usize = 1000;
+
+// Smaller than a market data message, so every message is assembled field by field
+const SMALL_READ_BUFFER_SIZE: usize = 128;
+
+fn market_data_message(seq_num: usize) -> Vec<u8> {
+    let body = format!(
+        "35=W\x0149=YOURBOURSE\x0156=CLIENT\x0134={}\x0152=20240425-17:28:02.629\x01262=1714066082629000\x0155=EURUSD\x01268=2\x01269=0\x01270=1.07012\x01271=1000000\x01269=1\x01270=1.07015\x01271=1000000\x01",
+        seq_num
+    );
+
+    build_frame(body.as_bytes())
+}
+
+fn feed() -> Vec<u8> {
+    let mut result = vec![];
+    for seq_num in 0..MESSAGES_COUNT {
+        result.extend_from_slice(market_data_message(seq_num).as_slice());
+    }
+    result
+}
+
+fn state() -> YbTcpSate {
+    YbTcpSate::new(YbFixSettings::new(
+        "fix.yourbourse.com:5001".to_string(),
+        "secret".to_string(),
+        "CLIENT".to_string(),
+        "YOURBOURSE".to_string(),
+    ))
+}
+
+// Same path as a live connection: receive_fix_payload and decoding of every message
+async fn deserialize_all(
+    serializer: &mut YourBourseFixTcpSerializer,
+    socket_reader: &mut SocketReaderInMem,
+    state: &YbTcpSate,
+) {
+    for _ in 0..MESSAGES_COUNT {
+        let message = serializer.deserialize(socket_reader, state).await.unwrap();
+        black_box(message);
+    }
+}
+
+// Framing as it was done before: one read per field into a new Vec and locate_frame
+// after every field. Kept as the baseline the serializer is compared with
+async fn deserialize_all_per_field(
+    buffer: &mut ReadBuffer,
+    socket_reader: &mut SocketReaderInMem,
+    fix_printer: &FixMessagePrinter,
+) {
+    for _ in 0..MESSAGES_COUNT {
+        let mut payload = vec![];
+
+        loop {
+            let field = socket_reader
+                .read_until_end_marker(buffer, b"\x01".as_slice())
+                .await
+                .unwrap();
+            payload.extend_from_slice(field);
+
+            if let Some(frame_len) = locate_frame(payload.as_slice()).unwrap() {
+                assert_eq!(frame_len, payload.len());
+                break;
+            }
+        }
+
+        black_box(FixMessage::from_slice_with_printer(
+            payload.as_slice(),
+            fix_printer,
+        ));
+    }
+}
+
+fn framing_benchmark(c: &mut Criterion) {
+    let feed = feed();
+    let state = state();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+
+    let mut group = c.benchmark_group("deserialize");
+    group.throughput(Throughput::Elements(MESSAGES_COUNT as u64));
+
+    group.bench_function("per_field_baseline", |b| {
+        b.iter_batched(
+            || {
+                (
+                    ReadBuffer::new(DEFAULT_READ_BUFFER_SIZE),
+                    SocketReaderInMem::new(feed.clone()),
+                )
+            },
+            |(mut buffer, mut socket_reader)| {
+                runtime.block_on(deserialize_all_per_field(
+                    &mut buffer,
+                    &mut socket_reader,
+                    state.logger.get_fix_printer(),
+                ))
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    for (name, read_buffer_size) in [
+        ("frame_at_once", DEFAULT_READ_BUFFER_SIZE),
+        ("field_by_field", SMALL_READ_BUFFER_SIZE),
+    ] {
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || {
+                    (
+                        YourBourseFixTcpSerializer::with_buffer_size(
+                            read_buffer_size,
+                            DEFAULT_MAX_MESSAGE_SIZE,
+                        ),
+                        SocketReaderInMem::new(feed.clone()),
+                    )
+                },
+                |(mut serializer, mut socket_reader)| {
+                    runtime.block_on(deserialize_all(&mut serializer, &mut socket_reader, &state))
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, framing_benchmark);
+criterion_main!(benches);
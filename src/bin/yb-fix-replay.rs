@@ -0,0 +1,195 @@
+use std::{
+    fs::File,
+    io::{BufReader, Write},
+    panic::AssertUnwindSafe,
+};
+
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+use yb_tcp_contracts::{
+    date_utils::parse_fix_date,
+    journal::{FixJournalDirection, FixJournalReader, FixJournalRecord},
+    FixMessagePrinter, YbIncomingMessage,
+};
+
+const USAGE: &str = "Usage: yb-fix-replay [--inbound-only] [--output <file>] \
+    [--from <YYYYMMDD-HH:MM:SS.fff>] [--to <YYYYMMDD-HH:MM:SS.fff>] <journal-file>...";
+
+struct ReplayArgs {
+    inbound_only: bool,
+    output: Option<String>,
+    from: Option<DateTimeAsMicroseconds>,
+    to: Option<DateTimeAsMicroseconds>,
+    files: Vec<String>,
+}
+
+impl ReplayArgs {
+    fn is_in_time_window(&self, timestamp: DateTimeAsMicroseconds) -> bool {
+        if let Some(from) = self.from {
+            if timestamp.unix_microseconds < from.unix_microseconds {
+                return false;
+            }
+        }
+
+        if let Some(to) = self.to {
+            if timestamp.unix_microseconds > to.unix_microseconds {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+fn main() {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}", err);
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let mut out: Box<dyn Write> = match args.output.as_ref() {
+        Some(path) => match File::create(path) {
+            Ok(file) => Box::new(file),
+            Err(err) => {
+                eprintln!("Can not create {}: {}", path, err);
+                std::process::exit(2);
+            }
+        },
+        None => Box::new(std::io::stdout()),
+    };
+
+    let fix_printer = FixMessagePrinter::default();
+    let mut failed = 0;
+
+    for file_name in args.files.iter() {
+        let file = match File::open(file_name) {
+            Ok(file) => file,
+            Err(err) => {
+                eprintln!("Can not open {}: {}", file_name, err);
+                failed += 1;
+                continue;
+            }
+        };
+
+        for (index, record) in FixJournalReader::new(BufReader::new(file)).enumerate() {
+            let record = match record {
+                Ok(record) => record,
+                Err(err) => {
+                    eprintln!("{}: record #{}: {}", file_name, index, err);
+                    failed += 1;
+                    break;
+                }
+            };
+
+            if args.inbound_only && record.direction != FixJournalDirection::Inbound {
+                continue;
+            }
+
+            if !args.is_in_time_window(record.timestamp) {
+                continue;
+            }
+
+            match replay_record(&record, &fix_printer) {
+                Ok(decoded) => {
+                    let result = writeln!(
+                        out,
+                        "{} {} {}",
+                        record.timestamp.to_rfc3339(),
+                        record.direction.as_str(),
+                        decoded
+                    );
+
+                    if let Err(err) = result {
+                        exit_on_write_error(err);
+                    }
+                }
+                Err(err) => {
+                    eprintln!(
+                        "{}: record #{}: parser panicked: {}. Message: {}",
+                        file_name,
+                        index,
+                        err,
+                        fix_printer.print(record.payload.as_slice())
+                    );
+                    failed += 1;
+                }
+            }
+        }
+    }
+
+    if let Err(err) = out.flush() {
+        exit_on_write_error(err);
+    }
+
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+fn exit_on_write_error(err: std::io::Error) -> ! {
+    eprintln!("Can not write output: {}", err);
+    std::process::exit(1);
+}
+
+// Goes through the same path as YourBourseFixTcpSerializer::deserialize
+fn replay_record(
+    record: &FixJournalRecord,
+    fix_printer: &FixMessagePrinter,
+) -> Result<String, String> {
+    let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        YbIncomingMessage::from_slice_with_printer(record.payload.as_slice(), fix_printer)
+            .to_string()
+    }));
+
+    result.map_err(|err| {
+        if let Some(err) = err.downcast_ref::<&str>() {
+            return err.to_string();
+        }
+
+        if let Some(err) = err.downcast_ref::<String>() {
+            return err.clone();
+        }
+
+        "unknown panic".to_string()
+    })
+}
+
+fn parse_args(mut src: impl Iterator<Item = String>) -> Result<ReplayArgs, String> {
+    let mut result = ReplayArgs {
+        inbound_only: false,
+        output: None,
+        from: None,
+        to: None,
+        files: vec![],
+    };
+
+    while let Some(arg) = src.next() {
+        match arg.as_str() {
+            "--inbound-only" => result.inbound_only = true,
+            "--output" => match src.next() {
+                Some(output) => result.output = Some(output),
+                None => return Err("--output requires a file name".to_string()),
+            },
+            "--from" => result.from = Some(parse_time_arg("--from", src.next())?),
+            "--to" => result.to = Some(parse_time_arg("--to", src.next())?),
+            _ if arg.starts_with("--") => return Err(format!("Unknown option {}", arg)),
+            _ => result.files.push(arg),
+        }
+    }
+
+    if result.files.is_empty() {
+        return Err("No journal files specified".to_string());
+    }
+
+    Ok(result)
+}
+
+fn parse_time_arg(name: &str, value: Option<String>) -> Result<DateTimeAsMicroseconds, String> {
+    match value {
+        Some(value) => parse_fix_date(&value).map_err(|err| err.to_string()),
+        None => Err(format!("{} requires a YYYYMMDD-HH:MM:SS.fff time", name)),
+    }
+}
@@ -0,0 +1,212 @@
+use std::{path::PathBuf, sync::Mutex};
+
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+pub const DEFAULT_MAX_CL_ORD_ID_LEN: usize = 36;
+
+// How many ids are reserved in the high-water mark file with one write
+const RESERVE_BLOCK: u64 = 1000;
+
+#[derive(Debug)]
+pub enum ClOrdIdError {
+    Empty,
+    TooLong { len: usize, max_len: usize },
+    InvalidChar(char),
+    Io(String),
+}
+
+impl std::fmt::Display for ClOrdIdError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "ClOrdID is empty"),
+            Self::TooLong { len, max_len } => write!(
+                f,
+                "ClOrdID is {} chars long. Max allowed length is {}",
+                len, max_len
+            ),
+            Self::InvalidChar(c) => write!(f, "ClOrdID contains invalid char '{}'", c),
+            Self::Io(err) => write!(f, "Can not persist ClOrdID high-water mark: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ClOrdIdError {}
+
+/// ClOrdID(11) may contain latin letters, digits and `-_.:` only
+pub fn validate_cl_ord_id(id: &str, max_len: usize) -> Result<(), ClOrdIdError> {
+    if id.is_empty() {
+        return Err(ClOrdIdError::Empty);
+    }
+
+    if id.len() > max_len {
+        return Err(ClOrdIdError::TooLong {
+            len: id.len(),
+            max_len,
+        });
+    }
+
+    for c in id.chars() {
+        if !is_valid_char(c) {
+            return Err(ClOrdIdError::InvalidChar(c));
+        }
+    }
+
+    Ok(())
+}
+
+fn is_valid_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' || c == ':'
+}
+
+struct ClOrdIdGeneratorState {
+    date: String,
+    next: u64,
+    reserved_until: u64,
+}
+
+/// Issues ClOrdID(11) values as `{YYYYMMDD}-{prefix}-{counter}`.
+/// The counter starts over every UTC day.
+///
+/// If a high-water mark file is given, ids are reserved in blocks and the end of the block
+/// is persisted before any id of the block is issued, so ids are never reused after a restart.
+pub struct ClOrdIdGenerator {
+    prefix: String,
+    high_water_mark_file: Option<PathBuf>,
+    state: Mutex<Option<ClOrdIdGeneratorState>>,
+}
+
+impl ClOrdIdGenerator {
+    pub fn new(prefix: impl Into<String>) -> Result<Self, ClOrdIdError> {
+        let prefix = prefix.into();
+
+        if let Some(c) = prefix.chars().find(|c| !is_valid_char(*c)) {
+            return Err(ClOrdIdError::InvalidChar(c));
+        }
+
+        Ok(Self {
+            prefix,
+            high_water_mark_file: None,
+            state: Mutex::new(None),
+        })
+    }
+
+    pub fn with_high_water_mark_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.high_water_mark_file = Some(path.into());
+        self
+    }
+
+    pub fn next(&self) -> Result<String, ClOrdIdError> {
+        self.next_at(DateTimeAsMicroseconds::now())
+    }
+
+    pub fn next_at(&self, now: DateTimeAsMicroseconds) -> Result<String, ClOrdIdError> {
+        let date = now.to_chrono_utc().format("%Y%m%d").to_string();
+
+        let mut state = self.state.lock().unwrap();
+
+        let is_new_date = match state.as_ref() {
+            Some(state) => state.date != date,
+            None => true,
+        };
+
+        if is_new_date {
+            let next = self.read_high_water_mark(&date)?;
+            *state = Some(ClOrdIdGeneratorState {
+                date: date.clone(),
+                next,
+                reserved_until: next,
+            });
+        }
+
+        let state = state.as_mut().unwrap();
+
+        if state.next >= state.reserved_until {
+            let reserved_until = state.next + RESERVE_BLOCK;
+            self.write_high_water_mark(&state.date, reserved_until)?;
+            state.reserved_until = reserved_until;
+        }
+
+        let id = state.next;
+        state.next += 1;
+
+        Ok(format!("{}-{}-{}", state.date, self.prefix, id))
+    }
+
+    fn read_high_water_mark(&self, date: &str) -> Result<u64, ClOrdIdError> {
+        let path = match self.high_water_mark_file.as_ref() {
+            Some(path) => path,
+            None => return Ok(1),
+        };
+
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(1),
+            Err(err) => return Err(ClOrdIdError::Io(err.to_string())),
+        };
+
+        match content.trim().split_once(' ') {
+            Some((stored_date, value)) if stored_date == date => value
+                .parse()
+                .map_err(|_| ClOrdIdError::Io(format!("Invalid high-water mark '{}'", content))),
+            Some(_) => Ok(1),
+            None => Err(ClOrdIdError::Io(format!(
+                "Invalid high-water mark '{}'",
+                content
+            ))),
+        }
+    }
+
+    fn write_high_water_mark(&self, date: &str, value: u64) -> Result<(), ClOrdIdError> {
+        let path = match self.high_water_mark_file.as_ref() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        // Written to a temp file first so a crash in the middle does not corrupt the mark
+        let tmp_path = path.with_extension("tmp");
+
+        std::fs::write(&tmp_path, format!("{} {}", date, value))
+            .and_then(|_| std::fs::rename(&tmp_path, path))
+            .map_err(|err| ClOrdIdError::Io(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2024-04-25T17:28:02.629Z
+    const NOW: i64 = 1714066082629000;
+
+    #[test]
+    fn test_validate_cl_ord_id() {
+        assert!(validate_cl_ord_id("20240425-A-1", DEFAULT_MAX_CL_ORD_ID_LEN).is_ok());
+        assert!(validate_cl_ord_id("", DEFAULT_MAX_CL_ORD_ID_LEN).is_err());
+        assert!(validate_cl_ord_id("order 1", DEFAULT_MAX_CL_ORD_ID_LEN).is_err());
+        assert!(validate_cl_ord_id("1234567890", 5).is_err());
+    }
+
+    #[test]
+    fn test_generator_resumes_after_restart() {
+        let path = std::env::temp_dir().join(format!(
+            "yb-cl-ord-id-{}.hwm",
+            DateTimeAsMicroseconds::now().unix_microseconds
+        ));
+        let now = DateTimeAsMicroseconds::new(NOW);
+
+        let generator = ClOrdIdGenerator::new("A")
+            .unwrap()
+            .with_high_water_mark_file(path.clone());
+        assert_eq!(generator.next_at(now).unwrap(), "20240425-A-1");
+        assert_eq!(generator.next_at(now).unwrap(), "20240425-A-2");
+
+        let restarted = ClOrdIdGenerator::new("A")
+            .unwrap()
+            .with_high_water_mark_file(path.clone());
+        let id = restarted.next_at(now).unwrap();
+
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(id, format!("20240425-A-{}", 1 + RESERVE_BLOCK));
+    }
+}
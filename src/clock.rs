@@ -0,0 +1,44 @@
+use std::sync::atomic::{AtomicI64, Ordering};
+
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+/// Source of SendingTime(52), TransactTime(60) and other time based values of outgoing messages
+pub trait FixClock: Send + Sync {
+    fn now(&self) -> DateTimeAsMicroseconds;
+}
+
+pub struct SystemFixClock;
+
+impl FixClock for SystemFixClock {
+    fn now(&self) -> DateTimeAsMicroseconds {
+        DateTimeAsMicroseconds::now()
+    }
+}
+
+/// Clock which returns the same moment until it's moved. Used by tests and replays
+pub struct FixedFixClock {
+    unix_microseconds: AtomicI64,
+}
+
+impl FixedFixClock {
+    pub fn new(now: DateTimeAsMicroseconds) -> Self {
+        Self {
+            unix_microseconds: AtomicI64::new(now.unix_microseconds),
+        }
+    }
+
+    pub fn set(&self, now: DateTimeAsMicroseconds) {
+        self.unix_microseconds
+            .store(now.unix_microseconds, Ordering::Relaxed);
+    }
+
+    pub fn advance_microseconds(&self, value: i64) {
+        self.unix_microseconds.fetch_add(value, Ordering::Relaxed);
+    }
+}
+
+impl FixClock for FixedFixClock {
+    fn now(&self) -> DateTimeAsMicroseconds {
+        DateTimeAsMicroseconds::new(self.unix_microseconds.load(Ordering::Relaxed))
+    }
+}
@@ -1,61 +1,283 @@
-use std::{fmt::Debug, str::FromStr};
+use std::str::FromStr;
 
+use chrono::{NaiveDate, NaiveTime};
 use rust_extensions::date_time::DateTimeAsMicroseconds;
+use serde::{Deserialize, Serialize};
+
+/// Fraction of a second written to SendingTime(52) and TransactTime(60)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FixTimestampPrecision {
+    Seconds,
+    #[default]
+    Milliseconds,
+    Microseconds,
+}
+
+impl FromStr for FixTimestampPrecision {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        match src {
+            "seconds" => Ok(Self::Seconds),
+            "milliseconds" => Ok(Self::Milliseconds),
+            "microseconds" => Ok(Self::Microseconds),
+            _ => Err(format!("Unknown timestamp precision '{}'", src)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixDateParseError {
+    pub data_type: &'static str,
+    pub src: String,
+}
+
+impl FixDateParseError {
+    fn new(data_type: &'static str, src: &str) -> Self {
+        Self {
+            data_type,
+            src: src.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for FixDateParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid {} value: '{}'", self.data_type, self.src)
+    }
+}
+
+impl std::error::Error for FixDateParseError {}
 
 pub fn to_fix_date_string(src: DateTimeAsMicroseconds) -> String {
+    to_fix_date_string_with_precision(src, FixTimestampPrecision::Milliseconds)
+}
+
+pub fn to_fix_date_string_with_precision(
+    src: DateTimeAsMicroseconds,
+    precision: FixTimestampPrecision,
+) -> String {
     let dt = src.to_chrono_utc();
-    dt.format("%Y%m%d-%H:%M:%S.%3f").to_string()
+
+    match precision {
+        FixTimestampPrecision::Seconds => dt.format("%Y%m%d-%H:%M:%S").to_string(),
+        FixTimestampPrecision::Milliseconds => dt.format("%Y%m%d-%H:%M:%S.%3f").to_string(),
+        FixTimestampPrecision::Microseconds => dt.format("%Y%m%d-%H:%M:%S.%6f").to_string(),
+    }
 }
 
-pub fn parse_fix_date(date: &str) -> DateTimeAsMicroseconds {
-    let year = parse_number(date, &date[0..4]);
-    let month = parse_number(date, &date[4..6]);
-    let day = parse_number(date, &date[6..8]);
-    let hour = parse_number(date, &date[9..11]);
-    let min = parse_number(date, &date[12..14]);
-    let sec = parse_number(date, &date[15..17]);
-    let micros: i64 = parse_number(date, &date[18..21]);
+/// UTCTimestamp: `YYYYMMDD-HH:MM:SS` with optional milli, micro or nanoseconds.
+/// Nanoseconds are truncated to microseconds
+pub fn parse_fix_date(date: &str) -> Result<DateTimeAsMicroseconds, FixDateParseError> {
+    let err = || FixDateParseError::new("UTCTimestamp", date);
+
+    let (date_part, time_part) = date.split_once('-').ok_or_else(err)?;
+    let date_part = parse_date(date_part.as_bytes()).ok_or_else(err)?;
+    let time_part = parse_time(time_part.as_bytes()).ok_or_else(err)?;
 
-    DateTimeAsMicroseconds::create(year, month, day, hour, min, sec, micros * 1000)
+    let micros = date_part.and_time(time_part).and_utc().timestamp_micros();
+    Ok(DateTimeAsMicroseconds::new(micros))
 }
 
-pub fn parse_tcp_feed_date(date: &[u8]) -> DateTimeAsMicroseconds {
-    let year = parse_number_from_slice(date, &date[0..4]);
-    let month = parse_number_from_slice(date, &date[4..6]);
-    let day = parse_number_from_slice(date, &date[6..8]);
-    let hour = parse_number_from_slice(date, &date[8..10]);
-    let min = parse_number_from_slice(date, &date[10..12]);
-    let sec = parse_number_from_slice(date, &date[12..14]);
-    let micros: i64 = parse_number_from_slice(date, &date[15..18]);
+/// UTCDateOnly: `YYYYMMDD`
+pub fn parse_fix_utc_date_only(date: &str) -> Result<NaiveDate, FixDateParseError> {
+    parse_date(date.as_bytes()).ok_or_else(|| FixDateParseError::new("UTCDateOnly", date))
+}
 
-    DateTimeAsMicroseconds::create(year, month, day, hour, min, sec, micros * 1000)
+/// UTCTimeOnly: `HH:MM:SS` with optional milli, micro or nanoseconds
+pub fn parse_fix_utc_time_only(time: &str) -> Result<NaiveTime, FixDateParseError> {
+    parse_time(time.as_bytes()).ok_or_else(|| FixDateParseError::new("UTCTimeOnly", time))
 }
 
-fn parse_number<TResult: FromStr + Debug>(date: &str, src: &str) -> TResult {
-    match src.parse() {
-        Ok(result) => result,
-        Err(_) => {
-            panic!("Unknown Date format: '{}'", date);
-        }
+/// LocalMktDate: `YYYYMMDD` in the local time of the market
+pub fn parse_fix_local_mkt_date(date: &str) -> Result<NaiveDate, FixDateParseError> {
+    parse_date(date.as_bytes()).ok_or_else(|| FixDateParseError::new("LocalMktDate", date))
+}
+
+fn parse_date(src: &[u8]) -> Option<NaiveDate> {
+    if src.len() != 8 {
+        return None;
     }
+
+    NaiveDate::from_ymd_opt(
+        parse_digits(&src[0..4])? as i32,
+        parse_digits(&src[4..6])?,
+        parse_digits(&src[6..8])?,
+    )
+}
+
+fn parse_time(src: &[u8]) -> Option<NaiveTime> {
+    if src.len() < 8 || src[2] != b':' || src[5] != b':' {
+        return None;
+    }
+
+    NaiveTime::from_hms_micro_opt(
+        parse_digits(&src[0..2])?,
+        parse_digits(&src[3..5])?,
+        parse_digits(&src[6..8])?,
+        parse_fraction(&src[8..])?,
+    )
 }
 
-fn parse_number_from_slice<TResult: FromStr + Debug>(date: &[u8], src: &[u8]) -> TResult {
-    unsafe {
-        parse_number(
-            std::str::from_utf8_unchecked(date),
-            std::str::from_utf8_unchecked(src),
-        )
+// Optional `.sss`, `.ssssss` or `.sssssssss` converted to microseconds
+fn parse_fraction(src: &[u8]) -> Option<u32> {
+    match src {
+        [] => Some(0),
+        [b'.', fraction @ ..] => match fraction.len() {
+            3 => Some(parse_digits(fraction)? * 1_000),
+            6 => parse_digits(fraction),
+            9 => Some(parse_digits(fraction)? / 1_000),
+            _ => None,
+        },
+        _ => None,
     }
 }
 
+fn parse_digits(src: &[u8]) -> Option<u32> {
+    if !src.iter().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let result = src
+        .iter()
+        .fold(0u32, |acc, b| acc * 10 + (*b - b'0') as u32);
+    Some(result)
+}
+
+/// Date of the price feed: `YYYYMMDDHHMMSS.fff` in UTC, e.g. `20240425172802.629`.
+/// Micro and nanoseconds are accepted as well, the fraction may be omitted.
+///
+/// There is no decoder of the price feed in this crate, so nothing here calls it
+pub fn parse_tcp_feed_date(date: &[u8]) -> Result<DateTimeAsMicroseconds, FixDateParseError> {
+    let err = || FixDateParseError::new("TcpFeedDate", &String::from_utf8_lossy(date));
+
+    if date.len() < 14 {
+        return Err(err());
+    }
+
+    let date_part = parse_date(&date[0..8]).ok_or_else(err)?;
+    let time_part = NaiveTime::from_hms_micro_opt(
+        parse_digits(&date[8..10]).ok_or_else(err)?,
+        parse_digits(&date[10..12]).ok_or_else(err)?,
+        parse_digits(&date[12..14]).ok_or_else(err)?,
+        parse_fraction(&date[14..]).ok_or_else(err)?,
+    )
+    .ok_or_else(err)?;
+
+    let micros = date_part.and_time(time_part).and_utc().timestamp_micros();
+    Ok(DateTimeAsMicroseconds::new(micros))
+}
+
 #[cfg(test)]
 mod tests {
 
     #[test]
     fn test_parse_date() {
         let date = "20240425-17:28:02.629";
-        let date: rust_extensions::date_time::DateTimeAsMicroseconds = super::parse_fix_date(date);
+        let date: rust_extensions::date_time::DateTimeAsMicroseconds =
+            super::parse_fix_date(date).unwrap();
         assert_eq!(&date.to_rfc3339()[..23], "2024-04-25T17:28:02.629");
     }
+
+    #[test]
+    fn test_parse_date_precisions() {
+        let cases = [
+            ("20240425-17:28:02", 1714066082000000),
+            ("20240425-17:28:02.629", 1714066082629000),
+            ("20240425-17:28:02.629123", 1714066082629123),
+            ("20240425-17:28:02.629123456", 1714066082629123),
+        ];
+
+        for (src, expected) in cases {
+            assert_eq!(
+                super::parse_fix_date(src).unwrap().unix_microseconds,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_precision_round_trip() {
+        use super::FixTimestampPrecision;
+
+        let now = rust_extensions::date_time::DateTimeAsMicroseconds::new(1714066082629123);
+
+        let cases = [
+            (
+                FixTimestampPrecision::Seconds,
+                "20240425-17:28:02",
+                1714066082000000,
+            ),
+            (
+                FixTimestampPrecision::Milliseconds,
+                "20240425-17:28:02.629",
+                1714066082629000,
+            ),
+            (
+                FixTimestampPrecision::Microseconds,
+                "20240425-17:28:02.629123",
+                1714066082629123,
+            ),
+        ];
+
+        for (precision, expected_str, expected) in cases {
+            let result = super::to_fix_date_string_with_precision(now, precision);
+            assert_eq!(result, expected_str);
+            assert_eq!(
+                super::parse_fix_date(&result).unwrap().unix_microseconds,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid_date_does_not_panic() {
+        for src in [
+            "",
+            "20240425",
+            "20240425-17:28",
+            "20240425-17:28:02.",
+            "20240425-17:28:02.6291",
+            "20241345-17:28:02",
+            "20240425-25:28:02",
+            "2024042Ж-17:28:02",
+            "+2024042-17:28:02",
+        ] {
+            assert!(super::parse_fix_date(src).is_err(), "{}", src);
+        }
+    }
+
+    #[test]
+    fn test_parse_tcp_feed_date() {
+        let date = super::parse_tcp_feed_date(b"20240425172802.629").unwrap();
+        assert_eq!(date.unix_microseconds, 1714066082629000);
+
+        let date = super::parse_tcp_feed_date(b"20240425172802").unwrap();
+        assert_eq!(date.unix_microseconds, 1714066082000000);
+
+        for src in [
+            &b""[..],
+            b"2024042517",
+            b"20240425172802.",
+            b"20240425172802.62",
+            b"20240425172802,629",
+            b"20240431172802.629",
+            b"2024042517280\xff.629",
+            b"\xff\xfe\xfd\xfc\xfb\xfa\xf9\xf8\xf7\xf6\xf5\xf4\xf3\xf2",
+        ] {
+            assert!(super::parse_tcp_feed_date(src).is_err());
+        }
+    }
+
+    #[test]
+    fn test_parse_date_only_and_time_only() {
+        let date = super::parse_fix_utc_date_only("20240425").unwrap();
+        assert_eq!(date.to_string(), "2024-04-25");
+        assert!(super::parse_fix_local_mkt_date("2024-04-25").is_err());
+
+        let time = super::parse_fix_utc_time_only("17:28:02.629123").unwrap();
+        assert_eq!(time.to_string(), "17:28:02.629123");
+        assert!(super::parse_fix_utc_time_only("17:28").is_err());
+    }
 }
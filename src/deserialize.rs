@@ -1,39 +1,58 @@
+use rust_decimal::Decimal;
 use rust_fix::FixMessageReader;
-use service_sdk::my_logger::LogEventCtx;
 
-use crate::tcp_messages::YbMarketData;
+use crate::{
+    fix_decimal::parse_fix_decimal, logging::YbFixLogContext, tcp_messages::YbMarketData,
+    FixMessagePrinter,
+};
 
-pub fn deserialize_market_data(fix_message: &FixMessageReader<'_>) -> Result<YbMarketData, String> {
+pub fn deserialize_market_data(
+    fix_message: &FixMessageReader<'_>,
+    fix_printer: &FixMessagePrinter,
+) -> Result<YbMarketData, String> {
     // there shall be always no_md_entries in the message
     // skip message if it's not exist
-    let no_md_entries = fix_message.get_value("268").unwrap();
+    let no_md_entries = fix_message
+        .get_value("268")
+        .map_err(|err| format!("Can not read tag 268: {:?}", err))?;
 
-    if no_md_entries == None {
-        service_sdk::my_logger::LOGGER.write_error(
-            String::from("FixMessageHandler"),
-            format!("268 tag not found: {}", fix_message.to_string()),
-            LogEventCtx::new(),
-        );
-        return Err("268 tag not found".to_string());
-    }
-    let no_md_entries = no_md_entries.unwrap().parse::<u32>().unwrap(); //.collect::<u32>().unwrap();
+    let no_md_entries = match no_md_entries {
+        Some(no_md_entries) => no_md_entries,
+        None => {
+            crate::logging::write_error(
+                "FixMessageHandler",
+                format!(
+                    "268 tag not found: {}",
+                    fix_printer.mask(&fix_message.to_string())
+                ),
+                YbFixLogContext::from_fix_message(fix_message),
+            );
+            return Err("268 tag not found".to_string());
+        }
+    };
+    let no_md_entries = no_md_entries
+        .parse::<u32>()
+        .map_err(|_| format!("Invalid value of tag 268: '{}'", no_md_entries))?;
 
     // not sure why buy sometimes there are no prices available in the message,
     // so we skip the message
     if no_md_entries < 2 {
-        service_sdk::my_logger::LOGGER.write_error(
-            String::from("FixMessageHandler"),
-            format!("Can not get md_entries: {}", fix_message.to_string()),
-            LogEventCtx::new(),
+        crate::logging::write_error(
+            "FixMessageHandler",
+            format!(
+                "Can not get md_entries: {}",
+                fix_printer.mask(&fix_message.to_string())
+            ),
+            YbFixLogContext::from_fix_message(fix_message),
         );
         return Err("md_entries less than 2".to_string());
     }
     let prices = fix_message
         .get_values("270")
-        .unwrap()
+        .map_err(|err| format!("Can not read tag 270: {:?}", err))?
         .iter()
-        .map(|x| x.parse::<f64>().unwrap())
-        .collect::<Vec<f64>>();
+        .map(|x| parse_fix_decimal(x).map_err(|err| format!("Invalid price '{}': {}", x, err)))
+        .collect::<Result<Vec<Decimal>, String>>()?;
 
     // I think the clients have to know that we do like this,
     // this may be a regulatory issue for them if they not aware
@@ -42,17 +61,72 @@ pub fn deserialize_market_data(fix_message: &FixMessageReader<'_>) -> Result<YbM
     //    false => (prices[1], prices[0]),
     //};
 
-    let (bid, ask) = (prices[0], prices[1]);
+    // NoMDEntries(268) may not match the number of entries actually sent
+    let (bid, ask) = match prices.as_slice() {
+        [bid, ask, ..] => (*bid, *ask),
+        _ => {
+            return Err(format!(
+                "Bid and ask expected, {} prices found",
+                prices.len()
+            ))
+        }
+    };
 
-    let external_market = fix_message.get_value("55").unwrap().unwrap();
-    let date_time = fix_message.get_value("52").unwrap().unwrap();
+    let external_market = read_required(fix_message, "55")?;
+    let date_time = read_required(fix_message, "52")?;
 
     let result = YbMarketData {
-        instrument_id: external_market.to_string(),
-        date: crate::date_utils::parse_fix_date(date_time),
+        instrument_id: external_market,
+        date: crate::date_utils::parse_fix_date(&date_time).map_err(|err| err.to_string())?,
         bid,
         ask,
     };
 
     Ok(result)
 }
+
+fn read_required(fix_message: &FixMessageReader<'_>, tag: &str) -> Result<String, String> {
+    match fix_message.get_value(tag) {
+        Ok(Some(value)) => Ok(value.to_string()),
+        Ok(None) => Err(format!("{} tag not found", tag)),
+        Err(err) => Err(format!("Can not read tag {}: {:?}", tag, err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deserialize(body: &str) -> Result<YbMarketData, String> {
+        let src = crate::tests_support::frame(body);
+        deserialize_market_data(
+            &FixMessageReader::from_bytes(src.as_slice()),
+            &FixMessagePrinter::default(),
+        )
+    }
+
+    #[test]
+    fn test_reads_bid_and_ask() {
+        let market_data = deserialize(concat!(
+            "35=W\x0152=20240425-17:28:02.629\x0155=EURUSD\x01268=2\x01",
+            "269=0\x01270=1.07012\x01269=1\x01270=1.07015\x01",
+        ))
+        .unwrap();
+
+        assert_eq!(market_data.instrument_id, "EURUSD");
+        assert_eq!(market_data.bid.to_string(), "1.07012");
+        assert_eq!(market_data.ask.to_string(), "1.07015");
+    }
+
+    #[test]
+    fn test_returns_error_on_malformed_market_data() {
+        for body in [
+            "35=W\x0152=20240425-17:28:02.629\x0155=EURUSD\x01268=X\x01",
+            "35=W\x0152=20240425-17:28:02.629\x0155=EURUSD\x01268=2\x01269=0\x01270=1.07012\x01",
+            "35=W\x0152=20240425-17:28:02.629\x01268=2\x01270=1.07012\x01270=1.07015\x01",
+            "35=W\x0155=EURUSD\x01268=2\x01270=1.07012\x01270=1.07015\x01",
+        ] {
+            assert!(deserialize(body).is_err(), "{}", body);
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use std::{sync::Mutex, time::Duration};
+
+use my_tcp_sockets::TcpClientSocketSettings;
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+use crate::{session_schedule::YbSessionSchedule, YbFixEndpoint, YbFixSettings};
+
+/// Endpoint the connection is on, for monitoring
+#[derive(Debug, Clone)]
+pub struct YbEndpointStatus {
+    pub url: String,
+    pub priority: u32,
+    //Logon was received on this endpoint
+    pub connected: bool,
+    pub selected_at: DateTimeAsMicroseconds,
+    //Rounds over all endpoints which ended without a Logon
+    pub failed_rounds: u32,
+}
+
+struct YbEndpointSelectorState {
+    next_index: usize,
+    failed_rounds: u32,
+    current: Option<YbEndpointStatus>,
+}
+
+/// Picks the gateway for every connection attempt of YbTcpSocketConnection.
+/// Endpoints are tried in the order of priority. Once all of them have failed,
+/// the next round is delayed with exponential backoff.
+/// After a successful Logon the next attempt starts over from the preferred endpoint.
+/// With a session schedule no connection is made until the session opens
+pub struct YbEndpointSelector {
+    endpoints: Vec<YbFixEndpoint>,
+    backoff_initial: Duration,
+    backoff_max: Duration,
+    session_schedule: Option<YbSessionSchedule>,
+    state: Mutex<YbEndpointSelectorState>,
+}
+
+impl YbEndpointSelector {
+    pub fn from_settings(settings: &YbFixSettings) -> Self {
+        let result = Self::new(
+            settings.get_endpoints(),
+            Duration::from_millis(settings.reconnect_backoff_initial_ms),
+            Duration::from_millis(settings.reconnect_backoff_max_ms),
+        );
+
+        match settings.session_schedule.as_ref() {
+            Some(schedule) => result.with_session_schedule(schedule.clone()),
+            None => result,
+        }
+    }
+
+    pub fn new(
+        endpoints: Vec<YbFixEndpoint>,
+        backoff_initial: Duration,
+        backoff_max: Duration,
+    ) -> Self {
+        if endpoints.is_empty() {
+            panic!("YbEndpointSelector requires at least one endpoint");
+        }
+
+        Self {
+            endpoints,
+            backoff_initial,
+            backoff_max,
+            session_schedule: None,
+            state: Mutex::new(YbEndpointSelectorState {
+                next_index: 0,
+                failed_rounds: 0,
+                current: None,
+            }),
+        }
+    }
+
+    pub fn with_session_schedule(mut self, session_schedule: YbSessionSchedule) -> Self {
+        self.session_schedule = Some(session_schedule);
+        self
+    }
+
+    /// Endpoint for the next connection attempt and the delay to wait before it
+    pub fn next_endpoint(&self) -> (YbFixEndpoint, Duration) {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(current) = state.current.as_ref() {
+            if current.connected {
+                state.next_index = 0;
+                state.failed_rounds = 0;
+            }
+        }
+
+        let mut delay = Duration::ZERO;
+
+        if state.next_index >= self.endpoints.len() {
+            state.next_index = 0;
+            state.failed_rounds += 1;
+            delay = self.get_backoff_delay(state.failed_rounds);
+        }
+
+        let endpoint = self.endpoints[state.next_index].clone();
+        state.next_index += 1;
+
+        state.current = Some(YbEndpointStatus {
+            url: endpoint.url.clone(),
+            priority: endpoint.priority,
+            connected: false,
+            selected_at: DateTimeAsMicroseconds::now(),
+            failed_rounds: state.failed_rounds,
+        });
+
+        (endpoint, delay)
+    }
+
+    /// Called when Logon is received on the current endpoint
+    pub fn report_connected(&self) {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(current) = state.current.as_mut() {
+            current.connected = true;
+            current.failed_rounds = 0;
+        }
+    }
+
+    pub fn get_current_endpoint(&self) -> Option<YbEndpointStatus> {
+        self.state.lock().unwrap().current.clone()
+    }
+
+    /// Zero while the session is open
+    pub fn get_delay_until_session_open(&self, now: DateTimeAsMicroseconds) -> Duration {
+        let schedule = match self.session_schedule.as_ref() {
+            Some(schedule) if !schedule.is_session_open(now) => schedule,
+            _ => return Duration::ZERO,
+        };
+
+        match schedule.get_next_session_start(now) {
+            Some(start) => {
+                Duration::from_micros((start.unix_microseconds - now.unix_microseconds) as u64)
+            }
+            None => Duration::ZERO,
+        }
+    }
+
+    fn get_backoff_delay(&self, failed_rounds: u32) -> Duration {
+        let multiplier = 2u32.saturating_pow(failed_rounds.saturating_sub(1));
+        self.backoff_initial
+            .saturating_mul(multiplier)
+            .min(self.backoff_max)
+    }
+}
+
+#[async_trait::async_trait]
+impl TcpClientSocketSettings for YbEndpointSelector {
+    async fn get_host_port(&self) -> String {
+        let (endpoint, delay) = self.next_endpoint();
+        let delay = delay.max(self.get_delay_until_session_open(DateTimeAsMicroseconds::now()));
+
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+
+        endpoint.url
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selector() -> YbEndpointSelector {
+        let mut settings = crate::tests_support::settings();
+        settings.url = "primary:5001".to_string();
+
+        settings.backup_endpoints = vec![YbFixEndpoint {
+            url: "backup:5001".to_string(),
+            priority: 1,
+        }];
+        settings.reconnect_backoff_initial_ms = 100;
+        settings.reconnect_backoff_max_ms = 250;
+
+        YbEndpointSelector::from_settings(&settings)
+    }
+
+    #[test]
+    fn test_fails_over_with_backoff() {
+        let selector = selector();
+
+        let attempts: Vec<(String, u128)> = (0..7)
+            .map(|_| selector.next_endpoint())
+            .map(|(endpoint, delay)| (endpoint.url, delay.as_millis()))
+            .collect();
+
+        assert_eq!(
+            attempts,
+            vec![
+                ("primary:5001".to_string(), 0),
+                ("backup:5001".to_string(), 0),
+                ("primary:5001".to_string(), 100),
+                ("backup:5001".to_string(), 0),
+                ("primary:5001".to_string(), 200),
+                ("backup:5001".to_string(), 0),
+                ("primary:5001".to_string(), 250),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_waits_for_session_to_open() {
+        let selector = selector().with_session_schedule(
+            "Europe/London;22:05:00;22:00:00;Sun,Mon,Tue,Wed,Thu"
+                .parse()
+                .unwrap(),
+        );
+
+        // Sat 2024-04-27 12:00 UTC, the session opens on Sun 21:05 UTC
+        let saturday = DateTimeAsMicroseconds::new(1714219200000000);
+        assert_eq!(
+            selector.get_delay_until_session_open(saturday),
+            Duration::from_secs(33 * 3600 + 5 * 60)
+        );
+
+        // Mon 2024-04-29 12:00 UTC
+        let monday = DateTimeAsMicroseconds::new(1714392000000000);
+        assert!(selector.get_delay_until_session_open(monday).is_zero());
+    }
+
+    #[test]
+    fn test_starts_over_from_primary_after_logon() {
+        let selector = selector();
+
+        selector.next_endpoint();
+        selector.next_endpoint();
+        selector.report_connected();
+
+        let current = selector.get_current_endpoint().unwrap();
+        assert_eq!(current.url, "backup:5001");
+        assert!(current.connected);
+
+        let (endpoint, delay) = selector.next_endpoint();
+        assert_eq!(endpoint.url, "primary:5001");
+        assert!(delay.is_zero());
+    }
+}
@@ -0,0 +1,44 @@
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+
+/// Accepts plain FIX float values and, to be lenient with counterparties, scientific notation
+pub fn parse_fix_decimal(src: &str) -> Result<Decimal, rust_decimal::Error> {
+    match Decimal::from_str(src) {
+        Ok(result) => Ok(result),
+        Err(err) => match Decimal::from_scientific(src) {
+            Ok(result) => Ok(result),
+            Err(_) => Err(err),
+        },
+    }
+}
+
+/// FIX float: no exponent and no trailing zeros
+pub fn to_fix_decimal_string(value: Decimal) -> String {
+    value.normalize().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_round_trip() {
+        for src in ["0.30000000000000004", "1.07015", "100", "0.0000001"] {
+            let value = super::parse_fix_decimal(src).unwrap();
+            assert_eq!(super::to_fix_decimal_string(value), src);
+        }
+    }
+
+    #[test]
+    fn test_formats_without_exponent() {
+        assert_eq!(
+            super::to_fix_decimal_string(Decimal::new(1, 7)),
+            "0.0000001"
+        );
+        assert_eq!(super::to_fix_decimal_string(Decimal::new(1500, 3)), "1.5");
+
+        let value = super::parse_fix_decimal("1e-7").unwrap();
+        assert_eq!(super::to_fix_decimal_string(value), "0.0000001");
+    }
+}
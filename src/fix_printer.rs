@@ -0,0 +1,105 @@
+use crate::FIX_DELIMITER;
+
+//Password, NewPassword, Username
+pub const DEFAULT_MASKED_TAGS: [&str; 3] = ["554", "925", "553"];
+
+const MASK: &str = "***";
+
+/// Renders FIX messages for logs replacing values of sensitive tags with `***`
+#[derive(Debug, Clone)]
+pub struct FixMessagePrinter {
+    masked_tags: Vec<String>,
+}
+
+impl FixMessagePrinter {
+    pub fn new(masked_tags: Vec<String>) -> Self {
+        Self { masked_tags }
+    }
+
+    /// Renders raw FIX bytes with `|` as a delimiter.
+    /// Fields are masked before SOH is replaced, so `|` inside of a masked value is masked too
+    pub fn print(&self, src: &[u8]) -> String {
+        self.mask(&String::from_utf8_lossy(src))
+            .replace(FIX_DELIMITER as char, "|")
+    }
+
+    /// Masks a message delimited with SOH or, if there is no SOH in it, an already rendered one
+    pub fn mask(&self, src: &str) -> String {
+        let delimiter = if src.contains(FIX_DELIMITER as char) {
+            FIX_DELIMITER as char
+        } else {
+            '|'
+        };
+
+        let mut result = String::with_capacity(src.len());
+
+        for field in src.split_inclusive(delimiter) {
+            let (body, field_end) = match field.strip_suffix(delimiter) {
+                Some(body) => (body, &field[body.len()..]),
+                None => (field, ""),
+            };
+
+            match body.split_once('=') {
+                Some((tag, _)) if self.is_masked(tag) => {
+                    result.push_str(tag);
+                    result.push('=');
+                    result.push_str(MASK);
+                }
+                _ => result.push_str(body),
+            }
+
+            result.push_str(field_end);
+        }
+
+        result
+    }
+
+    fn is_masked(&self, tag: &str) -> bool {
+        self.masked_tags.iter().any(|masked| masked == tag)
+    }
+}
+
+impl Default for FixMessagePrinter {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_MASKED_TAGS
+                .iter()
+                .map(|tag| tag.to_string())
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FixMessagePrinter;
+
+    #[test]
+    fn test_masks_sensitive_tags() {
+        let printer = FixMessagePrinter::default();
+        let src = b"8=FIX.4.4\x019=20\x0135=A\x01553=user\x01554=secret\x0110=123\x01";
+
+        assert_eq!(
+            printer.print(src),
+            "8=FIX.4.4|9=20|35=A|553=***|554=***|10=123|"
+        );
+    }
+
+    #[test]
+    fn test_masks_whole_value_with_pipe() {
+        let printer = FixMessagePrinter::default();
+        let src = b"8=FIX.4.4\x019=20\x0135=A\x01554=pa|ss\x0110=123\x01";
+
+        assert_eq!(printer.print(src), "8=FIX.4.4|9=20|35=A|554=***|10=123|");
+    }
+
+    #[test]
+    fn test_masks_rendered_message() {
+        let printer = FixMessagePrinter::new(vec!["554".to_string()]);
+
+        assert_eq!(
+            printer.mask("35=A|5540=keep|554=secret"),
+            "35=A|5540=keep|554=***"
+        );
+    }
+}
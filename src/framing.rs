@@ -0,0 +1,240 @@
+use crate::FIX_DELIMITER;
+
+// 10=XXX<SOH>
+pub const CHECKSUM_FIELD_LEN: usize = 7;
+// <SOH>10= - end of the last body field and the beginning of CheckSum(10)
+pub const CHECKSUM_TAG_MARKER: [u8; 4] = [FIX_DELIMITER, b'1', b'0', b'='];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FixFramingError {
+    InvalidBeginString,
+    InvalidBodyLength(String),
+    BodyLengthMismatch,
+    InvalidCheckSum(String),
+    CheckSumMismatch { expected: u8, calculated: u8 },
+    MessageTooLarge { size: usize, max_size: usize },
+}
+
+impl std::fmt::Display for FixFramingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidBeginString => write!(f, "Message does not start with BeginString(8)"),
+            Self::InvalidBodyLength(src) => write!(f, "Invalid BodyLength(9) field: '{}'", src),
+            Self::BodyLengthMismatch => {
+                write!(f, "BodyLength(9) does not match the message fields")
+            }
+            Self::InvalidCheckSum(src) => write!(f, "Invalid CheckSum(10) field: '{}'", src),
+            Self::CheckSumMismatch {
+                expected,
+                calculated,
+            } => write!(
+                f,
+                "CheckSum(10) mismatch. Expected: {:03}, calculated: {:03}",
+                expected, calculated
+            ),
+            Self::MessageTooLarge { size, max_size } => write!(
+                f,
+                "Message of {} bytes exceeds the limit of {} bytes",
+                size, max_size
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FixFramingError {}
+
+pub fn calculate_checksum(src: &[u8]) -> u8 {
+    src.iter().fold(0u8, |acc, b| acc.wrapping_add(*b))
+}
+
+/// Wraps body fields, starting with MsgType(35), into BeginString(8), BodyLength(9)
+/// and CheckSum(10). Used to build inbound messages for tests and benchmarks
+pub fn build_frame(body: &[u8]) -> Vec<u8> {
+    let mut result = format!("8={}\x019={}\x01", crate::OUR_FIX_VERSION, body.len()).into_bytes();
+    result.extend_from_slice(body);
+
+    let checksum = calculate_checksum(result.as_slice());
+    result.extend_from_slice(format!("10={:03}\x01", checksum).as_bytes());
+    result
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixFrameHeader {
+    //Length of BeginString(8) and BodyLength(9) fields
+    pub header_len: usize,
+    pub body_length: usize,
+}
+
+impl FixFrameHeader {
+    pub fn get_frame_len(&self) -> usize {
+        self.header_len + self.body_length + CHECKSUM_FIELD_LEN
+    }
+}
+
+/// Parses BeginString(8) and BodyLength(9) at the beginning of `src`.
+/// Returns `Ok(None)` if more bytes are required.
+pub fn parse_header(src: &[u8]) -> Result<Option<FixFrameHeader>, FixFramingError> {
+    if src.len() < 2 {
+        return Ok(None);
+    }
+
+    if !src.starts_with(b"8=") {
+        return Err(FixFramingError::InvalidBeginString);
+    }
+
+    let begin_string_end = match find_delimiter(src, 0) {
+        Some(index) => index + 1,
+        None => return Ok(None),
+    };
+
+    let body_length_field = &src[begin_string_end..];
+
+    if body_length_field.len() >= 2 && !body_length_field.starts_with(b"9=") {
+        return Err(FixFramingError::InvalidBodyLength(
+            String::from_utf8_lossy(body_length_field).to_string(),
+        ));
+    }
+
+    let header_end = match find_delimiter(src, begin_string_end) {
+        Some(index) => index + 1,
+        None => return Ok(None),
+    };
+
+    let body_length = parse_body_length(&src[begin_string_end + 2..header_end - 1])?;
+
+    Ok(Some(FixFrameHeader {
+        header_len: header_end,
+        body_length,
+    }))
+}
+
+/// Locates the first FIX message at the beginning of `src`.
+///
+/// Returns `Ok(None)` if more bytes are required, or the length of the message
+/// once BeginString(8), BodyLength(9) and CheckSum(10) are received and verified.
+pub fn locate_frame(src: &[u8]) -> Result<Option<usize>, FixFramingError> {
+    let header = match parse_header(src)? {
+        Some(header) => header,
+        None => return Ok(None),
+    };
+
+    let body_end = header.header_len + header.body_length;
+    let frame_end = header.get_frame_len();
+
+    if src.len() < frame_end {
+        return Ok(None);
+    }
+
+    let expected = parse_checksum_field(&src[body_end..frame_end])?;
+    let calculated = calculate_checksum(&src[..body_end]);
+
+    if expected != calculated {
+        return Err(FixFramingError::CheckSumMismatch {
+            expected,
+            calculated,
+        });
+    }
+
+    Ok(Some(frame_end))
+}
+
+fn find_delimiter(src: &[u8], from: usize) -> Option<usize> {
+    src[from..]
+        .iter()
+        .position(|b| *b == FIX_DELIMITER)
+        .map(|index| index + from)
+}
+
+fn parse_body_length(src: &[u8]) -> Result<usize, FixFramingError> {
+    let invalid = || FixFramingError::InvalidBodyLength(String::from_utf8_lossy(src).to_string());
+
+    // Anything above 999_999_999 bytes is garbage rather than a message
+    if src.is_empty() || src.len() > 9 || !src.iter().all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+
+    std::str::from_utf8(src)
+        .map_err(|_| invalid())?
+        .parse()
+        .map_err(|_| invalid())
+}
+
+fn parse_checksum_field(src: &[u8]) -> Result<u8, FixFramingError> {
+    let invalid = || FixFramingError::InvalidCheckSum(String::from_utf8_lossy(src).to_string());
+
+    if !src.starts_with(b"10=") || src[CHECKSUM_FIELD_LEN - 1] != FIX_DELIMITER {
+        return Err(invalid());
+    }
+
+    let digits = &src[3..CHECKSUM_FIELD_LEN - 1];
+
+    if !digits.iter().all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+
+    let value = digits
+        .iter()
+        .fold(0u16, |acc, b| acc * 10 + (*b - b'0') as u16);
+
+    if value > 255 {
+        return Err(invalid());
+    }
+
+    Ok(value as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEARTBEAT: &[u8] = b"8=FIX.4.4\x019=5\x0135=0\x0110=163\x01";
+
+    #[test]
+    fn test_locates_valid_frame() {
+        assert_eq!(locate_frame(HEARTBEAT), Ok(Some(HEARTBEAT.len())));
+
+        let mut two_messages = HEARTBEAT.to_vec();
+        two_messages.extend_from_slice(HEARTBEAT);
+        assert_eq!(locate_frame(&two_messages), Ok(Some(HEARTBEAT.len())));
+    }
+
+    #[test]
+    fn test_requires_more_bytes_for_partial_frame() {
+        for len in 0..HEARTBEAT.len() {
+            assert_eq!(locate_frame(&HEARTBEAT[..len]), Ok(None));
+        }
+    }
+
+    #[test]
+    fn test_builds_valid_frame() {
+        assert_eq!(build_frame(b"35=0\x01"), HEARTBEAT);
+    }
+
+    #[test]
+    fn test_detects_checksum_mismatch() {
+        let src = b"8=FIX.4.4\x019=5\x0135=1\x0110=163\x01";
+
+        assert_eq!(
+            locate_frame(src),
+            Err(FixFramingError::CheckSumMismatch {
+                expected: 163,
+                calculated: 164
+            })
+        );
+    }
+
+    #[test]
+    fn test_detects_wrong_body_length() {
+        let src = b"8=FIX.4.4\x019=4\x0135=0\x0110=163\x01";
+        assert!(matches!(
+            locate_frame(src),
+            Err(FixFramingError::InvalidCheckSum(_))
+        ));
+
+        let src = b"8=FIX.4.4\x019=X\x0135=0\x0110=163\x01";
+        assert!(matches!(
+            locate_frame(src),
+            Err(FixFramingError::InvalidBodyLength(_))
+        ));
+    }
+}
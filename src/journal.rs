@@ -0,0 +1,472 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::mpsc::{self, Receiver, Sender},
+    thread::JoinHandle,
+};
+
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+use crate::{logging::YbFixLogContext, DEFAULT_MAX_MESSAGE_SIZE};
+
+pub const DEFAULT_JOURNAL_MAX_FILE_SIZE: u64 = 256 * 1024 * 1024;
+pub const JOURNAL_FILE_EXTENSION: &str = "fixlog";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixJournalDirection {
+    Inbound,
+    Outbound,
+}
+
+impl FixJournalDirection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Inbound => "IN",
+            Self::Outbound => "OUT",
+        }
+    }
+}
+
+impl FromStr for FixJournalDirection {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        match src {
+            "IN" => Ok(Self::Inbound),
+            "OUT" => Ok(Self::Outbound),
+            _ => Err(format!("Unknown journal direction '{}'", src)),
+        }
+    }
+}
+
+/// Hook invoked with every FIX message exactly as it was sent or received
+pub trait FixJournal: Send + Sync {
+    fn write(
+        &self,
+        direction: FixJournalDirection,
+        timestamp: DateTimeAsMicroseconds,
+        payload: &[u8],
+    );
+}
+
+/// Writes messages into `{folder}/{prefix}-{YYYYMMDD}-{index}.fixlog`.
+/// A new file is started every UTC day and every time the current one exceeds `max_file_size`.
+///
+/// Every record is a header line `{IN|OUT} {unix_microseconds} {payload_len}\n`
+/// followed by the payload bytes and `\n`.
+///
+/// Records are written by a background thread, so `write` never blocks on the disk.
+/// The file is flushed whenever there is nothing left to write, and synced to disk
+/// when it is rolled over and when the journal is dropped
+pub struct RollingFileFixJournal {
+    folder: PathBuf,
+    sender: Option<Sender<FixJournalRecord>>,
+    writer_thread: Option<JoinHandle<()>>,
+}
+
+impl RollingFileFixJournal {
+    pub fn new(folder: impl Into<PathBuf>, file_prefix: impl Into<String>) -> Self {
+        Self::with_max_file_size(folder, file_prefix, DEFAULT_JOURNAL_MAX_FILE_SIZE)
+    }
+
+    pub fn with_max_file_size(
+        folder: impl Into<PathBuf>,
+        file_prefix: impl Into<String>,
+        max_file_size: u64,
+    ) -> Self {
+        let folder = folder.into();
+
+        let writer = RollingFileWriter {
+            folder: folder.clone(),
+            file_prefix: file_prefix.into(),
+            max_file_size,
+            current: None,
+        };
+
+        let (sender, receiver) = mpsc::channel();
+        let writer_thread = std::thread::spawn(move || writer.run(receiver));
+
+        Self {
+            folder,
+            sender: Some(sender),
+            writer_thread: Some(writer_thread),
+        }
+    }
+}
+
+impl FixJournal for RollingFileFixJournal {
+    fn write(
+        &self,
+        direction: FixJournalDirection,
+        timestamp: DateTimeAsMicroseconds,
+        payload: &[u8],
+    ) {
+        let record = FixJournalRecord {
+            direction,
+            timestamp,
+            payload: payload.to_vec(),
+        };
+
+        let sent = match self.sender.as_ref() {
+            Some(sender) => sender.send(record).is_ok(),
+            None => false,
+        };
+
+        if !sent {
+            report_write_error(
+                &self.folder,
+                format!("{} message is lost, writer is stopped", direction.as_str()),
+            );
+        }
+    }
+}
+
+// Waits until everything sent so far is written and synced
+impl Drop for RollingFileFixJournal {
+    fn drop(&mut self) {
+        self.sender.take();
+
+        if let Some(writer_thread) = self.writer_thread.take() {
+            let _ = writer_thread.join();
+        }
+    }
+}
+
+struct RollingFile {
+    file: BufWriter<File>,
+    date: String,
+    index: u32,
+    size: u64,
+}
+
+impl RollingFile {
+    fn close(mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        self.file.get_ref().sync_data()
+    }
+}
+
+// Lives on the background thread of RollingFileFixJournal
+struct RollingFileWriter {
+    folder: PathBuf,
+    file_prefix: String,
+    max_file_size: u64,
+    current: Option<RollingFile>,
+}
+
+impl RollingFileWriter {
+    fn run(mut self, receiver: Receiver<FixJournalRecord>) {
+        while let Ok(record) = receiver.recv() {
+            self.write(&record);
+
+            while let Ok(record) = receiver.try_recv() {
+                self.write(&record);
+            }
+
+            if let Some(current) = self.current.as_mut() {
+                if let Err(err) = current.file.flush() {
+                    report_write_error(&self.folder, err.to_string());
+                }
+            }
+        }
+
+        if let Some(current) = self.current.take() {
+            if let Err(err) = current.close() {
+                report_write_error(&self.folder, err.to_string());
+            }
+        }
+    }
+
+    fn write(&mut self, record: &FixJournalRecord) {
+        if let Err(err) = self.write_record(record) {
+            report_write_error(
+                &self.folder,
+                format!("{} message: {}", record.direction.as_str(), err),
+            );
+        }
+    }
+
+    fn get_file_path(&self, date: &str, index: u32) -> PathBuf {
+        self.folder.join(format!(
+            "{}-{}-{:04}.{}",
+            self.file_prefix, date, index, JOURNAL_FILE_EXTENSION
+        ))
+    }
+
+    fn open_file(&self, date: String, mut index: u32) -> std::io::Result<RollingFile> {
+        std::fs::create_dir_all(&self.folder)?;
+
+        loop {
+            let path = self.get_file_path(&date, index);
+            let size = match std::fs::metadata(&path) {
+                Ok(metadata) => metadata.len(),
+                Err(_) => 0,
+            };
+
+            if size >= self.max_file_size {
+                index += 1;
+                continue;
+            }
+
+            let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+            return Ok(RollingFile {
+                file: BufWriter::new(file),
+                date,
+                index,
+                size,
+            });
+        }
+    }
+
+    fn write_record(&mut self, record: &FixJournalRecord) -> std::io::Result<()> {
+        let date = record
+            .timestamp
+            .to_chrono_utc()
+            .format("%Y%m%d")
+            .to_string();
+
+        let roll_to = match self.current.as_ref() {
+            None => Some((date, 0)),
+            Some(file) if file.date != date => Some((date, 0)),
+            Some(file) if file.size >= self.max_file_size => Some((date, file.index + 1)),
+            Some(_) => None,
+        };
+
+        if let Some((date, index)) = roll_to {
+            if let Some(previous) = self.current.take() {
+                previous.close()?;
+            }
+
+            self.current = Some(self.open_file(date, index)?);
+        }
+
+        let file = self.current.as_mut().unwrap();
+
+        let header = format!(
+            "{} {} {}\n",
+            record.direction.as_str(),
+            record.timestamp.unix_microseconds,
+            record.payload.len()
+        );
+
+        file.file.write_all(header.as_bytes())?;
+        file.file.write_all(record.payload.as_slice())?;
+        file.file.write_all(b"\n")?;
+        file.size += (header.len() + record.payload.len() + 1) as u64;
+
+        Ok(())
+    }
+}
+
+fn report_write_error(folder: &Path, err: String) {
+    crate::logging::write_error(
+        "FixJournal",
+        format!("Can not write to journal {}: {}", folder.display(), err),
+        YbFixLogContext::default(),
+    );
+}
+
+#[derive(Debug, Clone)]
+pub struct FixJournalRecord {
+    pub direction: FixJournalDirection,
+    pub timestamp: DateTimeAsMicroseconds,
+    pub payload: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub enum FixJournalReadError {
+    Io(std::io::Error),
+    InvalidHeader { line: String },
+    TruncatedRecord { expected: usize },
+    RecordTooLarge { len: usize, max_len: usize },
+    MissingRecordEnd,
+}
+
+impl std::fmt::Display for FixJournalReadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "Can not read journal: {}", err),
+            Self::InvalidHeader { line } => write!(f, "Invalid journal record header: '{}'", line),
+            Self::TruncatedRecord { expected } => {
+                write!(
+                    f,
+                    "Journal record is truncated. Expected {} bytes",
+                    expected
+                )
+            }
+            Self::RecordTooLarge { len, max_len } => write!(
+                f,
+                "Journal record of {} bytes exceeds the limit of {} bytes",
+                len, max_len
+            ),
+            Self::MissingRecordEnd => write!(f, "Journal record is not followed by a new line"),
+        }
+    }
+}
+
+impl std::error::Error for FixJournalReadError {}
+
+/// Reads records written by [`RollingFileFixJournal`] back in the order they were written.
+/// A record longer than `max_record_len` is treated as a corrupt header
+pub struct FixJournalReader<TRead: BufRead> {
+    src: TRead,
+    max_record_len: usize,
+}
+
+impl<TRead: BufRead> FixJournalReader<TRead> {
+    pub fn new(src: TRead) -> Self {
+        Self::with_max_record_len(src, DEFAULT_MAX_MESSAGE_SIZE)
+    }
+
+    pub fn with_max_record_len(src: TRead, max_record_len: usize) -> Self {
+        Self {
+            src,
+            max_record_len,
+        }
+    }
+
+    fn read_record(&mut self) -> Result<Option<FixJournalRecord>, FixJournalReadError> {
+        let mut header = String::new();
+
+        let read = self
+            .src
+            .read_line(&mut header)
+            .map_err(FixJournalReadError::Io)?;
+
+        if read == 0 {
+            return Ok(None);
+        }
+
+        let (direction, timestamp, len) =
+            parse_record_header(header.trim_end()).ok_or_else(|| {
+                FixJournalReadError::InvalidHeader {
+                    line: header.trim_end().to_string(),
+                }
+            })?;
+
+        if len > self.max_record_len {
+            return Err(FixJournalReadError::RecordTooLarge {
+                len,
+                max_len: self.max_record_len,
+            });
+        }
+
+        let mut payload = vec![0u8; len + 1];
+
+        self.src
+            .read_exact(payload.as_mut_slice())
+            .map_err(|err| match err.kind() {
+                std::io::ErrorKind::UnexpectedEof => {
+                    FixJournalReadError::TruncatedRecord { expected: len }
+                }
+                _ => FixJournalReadError::Io(err),
+            })?;
+
+        //Each payload is followed by \n
+        if payload.pop() != Some(b'\n') {
+            return Err(FixJournalReadError::MissingRecordEnd);
+        }
+
+        Ok(Some(FixJournalRecord {
+            direction,
+            timestamp,
+            payload,
+        }))
+    }
+}
+
+impl<TRead: BufRead> Iterator for FixJournalReader<TRead> {
+    type Item = Result<FixJournalRecord, FixJournalReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+fn parse_record_header(src: &str) -> Option<(FixJournalDirection, DateTimeAsMicroseconds, usize)> {
+    let mut parts = src.split(' ');
+
+    let direction = parts.next()?.parse().ok()?;
+    let timestamp = DateTimeAsMicroseconds::new(parts.next()?.parse().ok()?);
+    let len = parts.next()?.parse().ok()?;
+
+    if parts.next().is_some() {
+        return None;
+    }
+
+    Some((direction, timestamp, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_and_read_back() {
+        let folder = std::env::temp_dir().join(format!(
+            "yb-fix-journal-{}",
+            DateTimeAsMicroseconds::now().unix_microseconds
+        ));
+
+        let journal = RollingFileFixJournal::new(folder.clone(), "TEST");
+        let timestamp = DateTimeAsMicroseconds::new(1714066082629000);
+
+        journal.write(
+            FixJournalDirection::Outbound,
+            timestamp,
+            b"8=FIX.4.4\x019=5\x0135=0\x0110=163\x01",
+        );
+        journal.write(
+            FixJournalDirection::Inbound,
+            timestamp,
+            b"8=FIX.4.4\x019=5\x0135=0\x01\n10=163\x01",
+        );
+
+        // Waits for the background writer
+        drop(journal);
+
+        let file_name = format!("TEST-20240425-0000.{}", JOURNAL_FILE_EXTENSION);
+        let file = std::fs::File::open(folder.join(file_name)).unwrap();
+
+        let records = FixJournalReader::new(std::io::BufReader::new(file))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        std::fs::remove_dir_all(folder).unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].direction, FixJournalDirection::Outbound);
+        assert_eq!(records[1].direction, FixJournalDirection::Inbound);
+        assert_eq!(
+            records[1].payload.as_slice(),
+            b"8=FIX.4.4\x019=5\x0135=0\x01\n10=163\x01"
+        );
+        assert_eq!(
+            records[0].timestamp.unix_microseconds,
+            timestamp.unix_microseconds
+        );
+    }
+
+    #[test]
+    fn test_rejects_corrupt_records() {
+        for src in [
+            // Length would overflow
+            &b"IN 1714066082629000 18446744073709551615\n"[..],
+            // Longer than max_record_len
+            b"IN 1714066082629000 2048\n",
+            // Payload is not followed by \n
+            b"IN 1714066082629000 3\n35=0\n",
+            b"IN 1714066082629000 3\n35",
+        ] {
+            let mut reader = FixJournalReader::with_max_record_len(src, 1024);
+            assert!(
+                reader.next().unwrap().is_err(),
+                "{}",
+                String::from_utf8_lossy(src)
+            );
+        }
+    }
+}
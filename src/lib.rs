@@ -8,18 +8,36 @@ use tcp_serializer::YourBourseFixTcpSerializer;
 pub use tcp_state::*;
 
 pub mod serialize;
+/// Its `send` is not throttled. Use `YbTcpSate::send` for application messages
 pub type YbTcpSocketConnection =
     TcpSocketConnection<tcp_messages::FixMessage, YourBourseFixTcpSerializer, YbTcpSate>;
 
 pub const OUR_FIX_VERSION: &'static str = "FIX.4.4";
 pub const FIX_DELIMITER: u8 = 0x1;
 pub mod date_utils;
+pub mod failover;
+mod settings;
+pub use settings::*;
+mod fix_printer;
+pub use fix_printer::*;
+pub mod cl_ord_id;
+pub mod clock;
+pub mod fix_decimal;
+pub mod framing;
+pub mod journal;
+pub mod logging;
+pub mod md_req_id;
+pub mod risk;
+pub mod session;
+pub mod session_callback;
+pub mod session_schedule;
+pub mod subscriptions;
+#[cfg(test)]
+mod tests_support;
+pub mod throttle;
+#[cfg(feature = "tls")]
+pub mod tls;
 
-const FIX_DELIMITER_AS_ARR: [u8; 1] = [FIX_DELIMITER];
+pub use rust_decimal::Decimal;
 
-pub struct YbFixSettings {
-    pub url: String,
-    pub password: String,
-    pub sender_company_id: String,
-    pub target_company_id: String,
-}
+const FIX_DELIMITER_AS_ARR: [u8; 1] = [FIX_DELIMITER];
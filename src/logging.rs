@@ -0,0 +1,160 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use rust_fix::FixMessageReader;
+use service_sdk::my_logger::LogEventCtx;
+
+use crate::{FixMessagePrinter, YbFixSettings};
+
+pub const DEBUG_FIX_ENV_VARIABLE: &str = "DEBUG_FIX";
+
+/// Session context attached to every log event written by the crate
+#[derive(Debug, Clone, Default)]
+pub struct YbFixLogContext {
+    pub sender_company_id: Option<String>,
+    pub target_company_id: Option<String>,
+    pub seq_num: Option<u64>,
+    pub msg_type: Option<String>,
+}
+
+impl YbFixLogContext {
+    pub fn from_settings(settings: &YbFixSettings) -> Self {
+        Self {
+            sender_company_id: Some(settings.sender_company_id.clone()),
+            target_company_id: Some(settings.target_company_id.clone()),
+            seq_num: None,
+            msg_type: None,
+        }
+    }
+
+    pub fn from_fix_message(src: &FixMessageReader) -> Self {
+        Self {
+            sender_company_id: read_tag(src, "49"),
+            target_company_id: read_tag(src, "56"),
+            seq_num: read_tag(src, "34").and_then(|src| src.parse().ok()),
+            msg_type: src.get_message_type().ok().map(|src| src.to_string()),
+        }
+    }
+
+    pub fn with_seq_num(mut self, seq_num: u64) -> Self {
+        self.seq_num = Some(seq_num);
+        self
+    }
+
+    pub fn with_msg_type(mut self, msg_type: &str) -> Self {
+        self.msg_type = Some(msg_type.to_string());
+        self
+    }
+
+    fn into_log_event_ctx(self) -> LogEventCtx {
+        let mut result = LogEventCtx::new();
+
+        if let Some(value) = self.sender_company_id {
+            result = result.add("senderCompId", value);
+        }
+
+        if let Some(value) = self.target_company_id {
+            result = result.add("targetCompId", value);
+        }
+
+        if let Some(value) = self.seq_num {
+            result = result.add("seqNum", value.to_string());
+        }
+
+        if let Some(value) = self.msg_type {
+            result = result.add("msgType", value);
+        }
+
+        result
+    }
+}
+
+/// Per session logger. Raw message tracing can be switched on and off at runtime
+pub struct YbFixLogger {
+    raw_trace: AtomicBool,
+    fix_printer: FixMessagePrinter,
+}
+
+impl YbFixLogger {
+    pub fn new(raw_trace: bool, fix_printer: FixMessagePrinter) -> Self {
+        Self {
+            raw_trace: AtomicBool::new(raw_trace),
+            fix_printer,
+        }
+    }
+
+    /// Raw trace is enabled if it's enabled in settings or `DEBUG_FIX` environment variable is set
+    pub fn from_settings(settings: &YbFixSettings) -> Self {
+        let raw_trace = settings.raw_trace || std::env::var(DEBUG_FIX_ENV_VARIABLE).is_ok();
+        Self::new(raw_trace, settings.get_fix_printer())
+    }
+
+    pub fn set_raw_trace(&self, value: bool) {
+        self.raw_trace.store(value, Ordering::Relaxed);
+    }
+
+    pub fn is_raw_trace_enabled(&self) -> bool {
+        self.raw_trace.load(Ordering::Relaxed)
+    }
+
+    pub fn get_fix_printer(&self) -> &FixMessagePrinter {
+        &self.fix_printer
+    }
+
+    /// Context is taken from the header of the traced message
+    pub fn trace_raw(&self, direction: &str, src: &[u8]) {
+        if !self.is_raw_trace_enabled() {
+            return;
+        }
+
+        let fix_message_reader = FixMessageReader::from_bytes(src);
+
+        write_info(
+            "RawFixMessage",
+            format!("{} Fix Message: {}", direction, self.fix_printer.print(src)),
+            YbFixLogContext::from_fix_message(&fix_message_reader),
+        );
+    }
+
+    pub fn write_info(&self, process: &str, message: String, ctx: YbFixLogContext) {
+        write_info(process, message, ctx);
+    }
+
+    pub fn write_warning(&self, process: &str, message: String, ctx: YbFixLogContext) {
+        write_warning(process, message, ctx);
+    }
+
+    pub fn write_error(&self, process: &str, message: String, ctx: YbFixLogContext) {
+        write_error(process, message, ctx);
+    }
+}
+
+pub fn write_info(process: &str, message: String, ctx: YbFixLogContext) {
+    service_sdk::my_logger::LOGGER.write_info(
+        process.to_string(),
+        message,
+        ctx.into_log_event_ctx(),
+    );
+}
+
+pub fn write_warning(process: &str, message: String, ctx: YbFixLogContext) {
+    service_sdk::my_logger::LOGGER.write_warning(
+        process.to_string(),
+        message,
+        ctx.into_log_event_ctx(),
+    );
+}
+
+pub fn write_error(process: &str, message: String, ctx: YbFixLogContext) {
+    service_sdk::my_logger::LOGGER.write_error(
+        process.to_string(),
+        message,
+        ctx.into_log_event_ctx(),
+    );
+}
+
+fn read_tag(src: &FixMessageReader, tag: &str) -> Option<String> {
+    match src.get_value(tag) {
+        Ok(Some(value)) => Some(value.to_string()),
+        _ => None,
+    }
+}
@@ -0,0 +1,42 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+/// Issues MDReqID(262) values as `{prefix}-{counter}`
+pub struct MdReqIdGenerator {
+    prefix: String,
+    counter: AtomicU64,
+}
+
+impl MdReqIdGenerator {
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            counter: AtomicU64::new(1),
+        }
+    }
+
+    /// Prefix is built from the session start time,
+    /// so ids issued before a restart are not reused after it
+    pub fn from_session_start(session_started: DateTimeAsMicroseconds) -> Self {
+        Self::new(format!("{}", session_started.unix_microseconds / 1000))
+    }
+
+    pub fn next(&self) -> String {
+        let id = self.counter.fetch_add(1, Ordering::Relaxed);
+        format!("{}-{}", self.prefix, id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MdReqIdGenerator;
+
+    #[test]
+    fn test_ids_are_unique() {
+        let generator = MdReqIdGenerator::new("S1");
+
+        assert_eq!(generator.next(), "S1-1");
+        assert_eq!(generator.next(), "S1-2");
+    }
+}
@@ -0,0 +1,343 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+};
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ExecutionReportModel, ExecutionReportModelStatus, PlaceOrderYbTcpContract,
+    PlaceOrderYbTcpContractSide, YbMarketData,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum YbRiskRejectReason {
+    SymbolNotAllowed(String),
+    MaxOrderQtyExceeded {
+        qty: Decimal,
+        max_qty: Decimal,
+    },
+    MaxNotionalExceeded {
+        notional: Decimal,
+        max_notional: Decimal,
+    },
+    MaxOpenOrdersReached {
+        max_open_orders: usize,
+    },
+    PriceOutOfBand {
+        price: Decimal,
+        reference_price: Decimal,
+    },
+    NoMarketData(String),
+    //Price band is configured, but the order has no expected_price to check
+    MissingExpectedPrice,
+}
+
+impl std::fmt::Display for YbRiskRejectReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SymbolNotAllowed(symbol) => write!(f, "Symbol {} is not allowed", symbol),
+            Self::MaxOrderQtyExceeded { qty, max_qty } => {
+                write!(f, "Order qty {} exceeds the limit of {}", qty, max_qty)
+            }
+            Self::MaxNotionalExceeded {
+                notional,
+                max_notional,
+            } => write!(
+                f,
+                "Order notional {} exceeds the limit of {}",
+                notional, max_notional
+            ),
+            Self::MaxOpenOrdersReached { max_open_orders } => {
+                write!(f, "{} orders are open already", max_open_orders)
+            }
+            Self::PriceOutOfBand {
+                price,
+                reference_price,
+            } => write!(
+                f,
+                "Price {} is too far from the market price {}",
+                price, reference_price
+            ),
+            Self::NoMarketData(symbol) => write!(f, "No market data for {}", symbol),
+            Self::MissingExpectedPrice => {
+                write!(f, "Expected price is required by the price band check")
+            }
+        }
+    }
+}
+
+impl std::error::Error for YbRiskRejectReason {}
+
+/// Run by `YbTcpSate::send`, which returns the reason of a reject to the caller,
+/// and again by the serializer right before PlaceOrder is written. A rejected order is not sent
+pub trait PreTradeCheck: Send + Sync {
+    /// An accepted order is counted as open from this moment,
+    /// so concurrent orders can not get past the limits together.
+    /// The same order is checked twice, so an order accepted already must be accepted again
+    fn check(&self, order: &PlaceOrderYbTcpContract) -> Result<(), YbRiskRejectReason>;
+
+    fn on_order_sent(&self, _order: &PlaceOrderYbTcpContract) {}
+
+    /// Accepted order was not sent after all: rejected by a later check or not connected
+    fn on_order_not_sent(&self, _order: &PlaceOrderYbTcpContract) {}
+
+    fn on_market_data(&self, _market_data: &YbMarketData) {}
+
+    fn on_execution_report(&self, _execution_report: &ExecutionReportModel) {}
+}
+
+/// Every limit is optional and not checked if not set
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct YbRiskLimits {
+    #[serde(default)]
+    pub allowed_symbols: Option<Vec<String>>,
+    #[serde(default)]
+    pub max_order_qty: Option<Decimal>,
+    //qty multiplied by the last ask for Buy and the last bid for Sell
+    #[serde(default)]
+    pub max_notional: Option<Decimal>,
+    #[serde(default)]
+    pub max_open_orders: Option<usize>,
+    //Max distance of the expected price of the order from the market price, in percent
+    #[serde(default)]
+    pub max_price_deviation_percent: Option<Decimal>,
+}
+
+/// Checks orders against YbRiskLimits. Market prices and open orders are tracked
+/// from the messages passing through the session
+pub struct YbPreTradeRiskChecker {
+    limits: YbRiskLimits,
+    //bid and ask by symbol
+    last_prices: Mutex<HashMap<String, (Decimal, Decimal)>>,
+    open_orders: Mutex<HashSet<String>>,
+}
+
+impl YbPreTradeRiskChecker {
+    pub fn new(limits: YbRiskLimits) -> Self {
+        Self {
+            limits,
+            last_prices: Mutex::new(HashMap::new()),
+            open_orders: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn get_open_orders_count(&self) -> usize {
+        self.open_orders.lock().unwrap().len()
+    }
+
+    fn get_reference_price(&self, order: &PlaceOrderYbTcpContract) -> Option<Decimal> {
+        let last_prices = self.last_prices.lock().unwrap();
+        let (bid, ask) = last_prices.get(&order.symbol)?;
+
+        match order.side {
+            PlaceOrderYbTcpContractSide::Buy => Some(*ask),
+            PlaceOrderYbTcpContractSide::Sell => Some(*bid),
+        }
+    }
+}
+
+impl PreTradeCheck for YbPreTradeRiskChecker {
+    fn check(&self, order: &PlaceOrderYbTcpContract) -> Result<(), YbRiskRejectReason> {
+        if let Some(allowed_symbols) = self.limits.allowed_symbols.as_ref() {
+            if !allowed_symbols.contains(&order.symbol) {
+                return Err(YbRiskRejectReason::SymbolNotAllowed(order.symbol.clone()));
+            }
+        }
+
+        if let Some(max_qty) = self.limits.max_order_qty {
+            if order.qty > max_qty {
+                return Err(YbRiskRejectReason::MaxOrderQtyExceeded {
+                    qty: order.qty,
+                    max_qty,
+                });
+            }
+        }
+
+        let reference_price = self.get_reference_price(order);
+
+        if let Some(max_notional) = self.limits.max_notional {
+            let reference_price = reference_price
+                .ok_or_else(|| YbRiskRejectReason::NoMarketData(order.symbol.clone()))?;
+
+            let notional = order.qty * reference_price;
+
+            if notional > max_notional {
+                return Err(YbRiskRejectReason::MaxNotionalExceeded {
+                    notional,
+                    max_notional,
+                });
+            }
+        }
+
+        if let Some(max_deviation) = self.limits.max_price_deviation_percent {
+            let price = order
+                .expected_price
+                .ok_or(YbRiskRejectReason::MissingExpectedPrice)?;
+
+            let reference_price = reference_price
+                .ok_or_else(|| YbRiskRejectReason::NoMarketData(order.symbol.clone()))?;
+
+            let deviation = (price - reference_price).abs() * Decimal::ONE_HUNDRED;
+
+            if deviation > max_deviation * reference_price {
+                return Err(YbRiskRejectReason::PriceOutOfBand {
+                    price,
+                    reference_price,
+                });
+            }
+        }
+
+        // Checked and reserved under one lock. Order which is open already is not counted twice
+        let mut open_orders = self.open_orders.lock().unwrap();
+
+        if !open_orders.contains(&order.id) {
+            if let Some(max_open_orders) = self.limits.max_open_orders {
+                if open_orders.len() >= max_open_orders {
+                    return Err(YbRiskRejectReason::MaxOpenOrdersReached { max_open_orders });
+                }
+            }
+
+            open_orders.insert(order.id.clone());
+        }
+
+        Ok(())
+    }
+
+    fn on_order_not_sent(&self, order: &PlaceOrderYbTcpContract) {
+        self.open_orders.lock().unwrap().remove(&order.id);
+    }
+
+    fn on_market_data(&self, market_data: &YbMarketData) {
+        self.last_prices.lock().unwrap().insert(
+            market_data.instrument_id.clone(),
+            (market_data.bid, market_data.ask),
+        );
+    }
+
+    fn on_execution_report(&self, execution_report: &ExecutionReportModel) {
+        match execution_report.ord_status {
+            ExecutionReportModelStatus::Filled
+            | ExecutionReportModelStatus::Canceled
+            | ExecutionReportModelStatus::Rejected => {
+                self.open_orders
+                    .lock()
+                    .unwrap()
+                    .remove(&execution_report.internal_order_id);
+            }
+            ExecutionReportModelStatus::New
+            | ExecutionReportModelStatus::PendingNew
+            | ExecutionReportModelStatus::PartiallyFilled => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+    use super::*;
+
+    fn order(id: &str, qty: Decimal, expected_price: Option<Decimal>) -> PlaceOrderYbTcpContract {
+        PlaceOrderYbTcpContract {
+            id: id.to_string(),
+            symbol: "EURUSD".to_string(),
+            side: PlaceOrderYbTcpContractSide::Buy,
+            qty,
+            expected_price,
+        }
+    }
+
+    fn checker() -> YbPreTradeRiskChecker {
+        let checker = YbPreTradeRiskChecker::new(YbRiskLimits {
+            allowed_symbols: Some(vec!["EURUSD".to_string()]),
+            max_order_qty: Some(Decimal::new(10, 0)),
+            max_notional: Some(Decimal::new(5, 0)),
+            max_open_orders: Some(1),
+            max_price_deviation_percent: Some(Decimal::new(1, 0)),
+        });
+
+        checker.on_market_data(&YbMarketData {
+            instrument_id: "EURUSD".to_string(),
+            date: DateTimeAsMicroseconds::new(0),
+            bid: Decimal::new(10700, 4),
+            ask: Decimal::new(10702, 4),
+        });
+
+        checker
+    }
+
+    #[test]
+    fn test_rejects_with_typed_reason() {
+        let checker = checker();
+
+        let mut other_symbol = order("1", Decimal::ONE, None);
+        other_symbol.symbol = "GBPUSD".to_string();
+        assert_eq!(
+            checker.check(&other_symbol),
+            Err(YbRiskRejectReason::SymbolNotAllowed("GBPUSD".to_string()))
+        );
+
+        assert!(matches!(
+            checker.check(&order("1", Decimal::new(11, 0), None)),
+            Err(YbRiskRejectReason::MaxOrderQtyExceeded { .. })
+        ));
+
+        assert!(matches!(
+            checker.check(&order("1", Decimal::new(5, 0), None)),
+            Err(YbRiskRejectReason::MaxNotionalExceeded { .. })
+        ));
+
+        assert!(matches!(
+            checker.check(&order("1", Decimal::ONE, Some(Decimal::new(1090, 3)))),
+            Err(YbRiskRejectReason::PriceOutOfBand { .. })
+        ));
+
+        assert!(checker
+            .check(&order("1", Decimal::ONE, Some(Decimal::new(1071, 3))))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_tracks_checked_orders_only() {
+        let checker = checker();
+        let price = Some(Decimal::new(1071, 3));
+
+        // Only a check reserves a slot
+        checker.on_order_sent(&order("1", Decimal::ONE, price));
+        assert_eq!(checker.get_open_orders_count(), 0);
+
+        assert!(checker.check(&order("1", Decimal::ONE, price)).is_ok());
+        assert_eq!(
+            checker.check(&order("2", Decimal::ONE, price)),
+            Err(YbRiskRejectReason::MaxOpenOrdersReached { max_open_orders: 1 })
+        );
+    }
+
+    #[test]
+    fn test_check_reserves_open_order() {
+        let checker = checker();
+        let price = Some(Decimal::new(1071, 3));
+
+        assert!(checker.check(&order("1", Decimal::ONE, price)).is_ok());
+        assert_eq!(checker.get_open_orders_count(), 1);
+
+        assert!(checker.check(&order("2", Decimal::ONE, price)).is_err());
+        assert!(checker.check(&order("1", Decimal::ONE, price)).is_ok());
+
+        checker.on_order_not_sent(&order("1", Decimal::ONE, price));
+        assert!(checker.check(&order("2", Decimal::ONE, price)).is_ok());
+    }
+
+    #[test]
+    fn test_price_band_requires_expected_price() {
+        let checker = checker();
+
+        assert_eq!(
+            checker.check(&order("1", Decimal::ONE, None)),
+            Err(YbRiskRejectReason::MissingExpectedPrice)
+        );
+        assert_eq!(checker.get_open_orders_count(), 0);
+    }
+}
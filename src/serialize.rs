@@ -1,26 +1,120 @@
 use rust_extensions::date_time::DateTimeAsMicroseconds;
 use rust_fix::FixMessageWriter;
 
-use crate::{tcp_messages::PlaceOrderYbTcpContract, YbFixSettings, OUR_FIX_VERSION};
+use crate::{
+    cl_ord_id::{validate_cl_ord_id, ClOrdIdError},
+    date_utils::to_fix_date_string_with_precision,
+    fix_decimal::to_fix_decimal_string,
+    tcp_messages::{
+        InstrumentSubscribeYbTcpContract, OrderMassStatusRequestYbTcpContract,
+        PlaceOrderYbTcpContract, YbOutgoingMessage,
+    },
+    YbFixSettings, OUR_FIX_VERSION,
+};
 
-pub fn logon(settings: &YbFixSettings, count: u64) -> FixMessageWriter {
-    let now = DateTimeAsMicroseconds::now();
-    let date_string = crate::date_utils::to_fix_date_string(now);
+#[derive(Debug)]
+pub enum YbSerializeError {
+    NotOutgoingMessage(String),
+    InvalidClOrdId { id: String, err: ClOrdIdError },
+}
+
+impl std::fmt::Display for YbSerializeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotOutgoingMessage(contract) => write!(
+                f,
+                "Contract {} can not be used as outgoing fix message",
+                contract
+            ),
+            Self::InvalidClOrdId { id, err } => {
+                write!(f, "Can not place order '{}': {}", id, err)
+            }
+        }
+    }
+}
+
+impl std::error::Error for YbSerializeError {}
+
+/// Checks what YourBourse would reject anyway. Must be called before a sequence number is taken
+pub fn validate_contract(
+    settings: &YbFixSettings,
+    contract: &YbOutgoingMessage,
+) -> Result<(), YbSerializeError> {
+    match contract {
+        YbOutgoingMessage::PlaceOrder(contract) => {
+            validate_cl_ord_id(&contract.id, settings.max_cl_ord_id_len).map_err(|err| {
+                YbSerializeError::InvalidClOrdId {
+                    id: contract.id.clone(),
+                    err,
+                }
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// `reset_seq_num` is the ResetSeqNumFlag(141) of Logon and is ignored for other messages
+pub fn serialize_contract(
+    settings: &YbFixSettings,
+    contract: &YbOutgoingMessage,
+    count: u64,
+    reset_seq_num: bool,
+    now: DateTimeAsMicroseconds,
+) -> FixMessageWriter {
+    match contract {
+        YbOutgoingMessage::InstrumentSubscribe(contract) => {
+            instrument_subscribe(settings, contract, count, now)
+        }
+        YbOutgoingMessage::InstrumentUnsubscribe(contract) => {
+            instrument_unsubscribe(settings, contract, count, now)
+        }
+        YbOutgoingMessage::Logon => logon(settings, count, reset_seq_num, now),
+        YbOutgoingMessage::Ping => ping(settings, count, now),
+        YbOutgoingMessage::PlaceOrder(contract) => {
+            place_order_contract(settings, contract, count, now)
+        }
+        YbOutgoingMessage::OrderMassStatusRequest(contract) => {
+            order_mass_status_request(settings, contract, count, now)
+        }
+    }
+}
+
+pub fn logon(
+    settings: &YbFixSettings,
+    count: u64,
+    reset_seq_num: bool,
+    now: DateTimeAsMicroseconds,
+) -> FixMessageWriter {
+    let date_string = to_fix_date_string_with_precision(now, settings.timestamp_precision);
 
     let mut fix_builder = FixMessageWriter::new(OUR_FIX_VERSION, "A");
     fill_from_settings(&mut fix_builder, settings, count, date_string.as_str());
-    fix_builder.with_value("108", "30");
-    fix_builder.with_value("141", "Y");
-    fix_builder.with_value("554", &settings.password);
+    //EncryptMethod - None
     fix_builder.with_value("98", "0");
+    //HeartBtInt
+    fix_builder.with_value("108", settings.heartbeat_interval_sec.to_string().as_str());
+    //ResetSeqNumFlag
+    if reset_seq_num {
+        fix_builder.with_value("141", "Y");
+    } else {
+        fix_builder.with_value("141", "N");
+    }
+    //Username
+    if let Some(username) = settings.username.as_ref() {
+        fix_builder.with_value("553", username);
+    }
+    //Password
+    fix_builder.with_value("554", &settings.password);
+
+    for (tag, value) in settings.extra_logon_tags.iter() {
+        fix_builder.with_value(tag, value);
+    }
 
-    println!("Logon message: {}", fix_builder.to_string());
     return fix_builder;
 }
 
-pub fn ping(settings: &YbFixSettings, count: u64) -> FixMessageWriter {
-    let now = DateTimeAsMicroseconds::now();
-    let date_string = crate::date_utils::to_fix_date_string(now);
+pub fn ping(settings: &YbFixSettings, count: u64, now: DateTimeAsMicroseconds) -> FixMessageWriter {
+    let date_string = to_fix_date_string_with_precision(now, settings.timestamp_precision);
 
     let mut fix_builder = FixMessageWriter::new(OUR_FIX_VERSION, "0");
 
@@ -31,14 +125,34 @@ pub fn ping(settings: &YbFixSettings, count: u64) -> FixMessageWriter {
 
 pub fn instrument_subscribe(
     settings: &YbFixSettings,
-    instrument: &str,
+    contract: &InstrumentSubscribeYbTcpContract,
+    count: u64,
+    now: DateTimeAsMicroseconds,
+) -> FixMessageWriter {
+    //SubscriptionRequestType 1 = Snapshot + Updates
+    market_data_request(settings, contract, "1", count, now)
+}
+
+pub fn instrument_unsubscribe(
+    settings: &YbFixSettings,
+    contract: &InstrumentSubscribeYbTcpContract,
     count: u64,
+    now: DateTimeAsMicroseconds,
 ) -> FixMessageWriter {
-    let now = DateTimeAsMicroseconds::now();
-    let date_string = crate::date_utils::to_fix_date_string(now);
+    //SubscriptionRequestType 2 = Disable previous Snapshot + Update Request
+    market_data_request(settings, contract, "2", count, now)
+}
+
+fn market_data_request(
+    settings: &YbFixSettings,
+    contract: &InstrumentSubscribeYbTcpContract,
+    subscription_request_type: &str,
+    count: u64,
+    now: DateTimeAsMicroseconds,
+) -> FixMessageWriter {
+    let date_string = to_fix_date_string_with_precision(now, settings.timestamp_precision);
 
     let mut fix_builder = FixMessageWriter::new(OUR_FIX_VERSION, "V");
-    let uuid = now.unix_microseconds;
 
     /*
        fix_builder.with_value("49", &settings.sender_company_id);
@@ -48,10 +162,10 @@ pub fn instrument_subscribe(
     */
     fill_from_settings(&mut fix_builder, settings, count, date_string.as_str());
 
-    //MDReqID - can be just a symbol name
-    fix_builder.with_value("262", &uuid.to_string());
-    //SubscriptionRequestType 1 = Snapshot + Updates
-    fix_builder.with_value("263", "1");
+    //MDReqID
+    fix_builder.with_value("262", &contract.md_req_id);
+    //SubscriptionRequestType
+    fix_builder.with_value("263", subscription_request_type);
     //Market Depth 1 = Top of Book
     fix_builder.with_value("264", "1");
     //MDUpdateType
@@ -65,7 +179,7 @@ pub fn instrument_subscribe(
     //NoRelatedSym
     fix_builder.with_value("146", "1");
     //Symbol
-    fix_builder.with_value("55", instrument);
+    fix_builder.with_value("55", &contract.symbol);
 
     return fix_builder;
 }
@@ -74,9 +188,9 @@ pub fn place_order_contract(
     settings: &YbFixSettings,
     contract: &PlaceOrderYbTcpContract,
     count: u64,
+    now: DateTimeAsMicroseconds,
 ) -> FixMessageWriter {
-    let now = DateTimeAsMicroseconds::now();
-    let date_string = crate::date_utils::to_fix_date_string(now);
+    let date_string = to_fix_date_string_with_precision(now, settings.timestamp_precision);
 
     let mut fix_builder = FixMessageWriter::new(OUR_FIX_VERSION, "D");
 
@@ -90,7 +204,7 @@ pub fn place_order_contract(
     //Side
     fix_builder.with_value("54", &(contract.side.clone() as i32).to_string());
     //OrderQty
-    fix_builder.with_value("38", contract.qty.to_string().as_str());
+    fix_builder.with_value("38", to_fix_decimal_string(contract.qty).as_str());
     //OrdType - market
     fix_builder.with_value("40", "1");
     //TimeInForce - IOC
@@ -101,6 +215,25 @@ pub fn place_order_contract(
     return fix_builder;
 }
 
+pub fn order_mass_status_request(
+    settings: &YbFixSettings,
+    contract: &OrderMassStatusRequestYbTcpContract,
+    count: u64,
+    now: DateTimeAsMicroseconds,
+) -> FixMessageWriter {
+    let date_string = to_fix_date_string_with_precision(now, settings.timestamp_precision);
+
+    let mut fix_builder = FixMessageWriter::new(OUR_FIX_VERSION, "AF");
+
+    fill_from_settings(&mut fix_builder, settings, count, date_string.as_str());
+    //MassStatusReqID
+    fix_builder.with_value("584", &contract.mass_status_req_id);
+    //MassStatusReqType 7 = Status for all orders
+    fix_builder.with_value("585", "7");
+
+    fix_builder
+}
+
 fn fill_from_settings(
     fix_builder: &mut FixMessageWriter,
     settings: &YbFixSettings,
@@ -111,4 +244,168 @@ fn fill_from_settings(
     fix_builder.with_value("52", date_string);
     fix_builder.with_value("56", &settings.target_company_id);
     fix_builder.with_value("34", count.to_string().as_str());
+
+    if let Some(sender_sub_id) = settings.sender_sub_id.as_ref() {
+        fix_builder.with_value("50", sender_sub_id);
+    }
+
+    if let Some(target_sub_id) = settings.target_sub_id.as_ref() {
+        fix_builder.with_value("57", target_sub_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_extensions::date_time::DateTimeAsMicroseconds;
+    use rust_fix::{FixMessageReader, FixMessageWriter};
+
+    use crate::{
+        date_utils::{parse_fix_date, FixTimestampPrecision},
+        InstrumentSubscribeYbTcpContract, OrderMassStatusRequestYbTcpContract,
+        PlaceOrderYbTcpContract, PlaceOrderYbTcpContractSide, YbFixSettings,
+    };
+
+    // 2024-04-25T17:28:02.629Z
+    const NOW: i64 = 1714066082629000;
+
+    fn settings() -> YbFixSettings {
+        crate::tests_support::settings()
+    }
+
+    fn compile(fix_builder: FixMessageWriter) -> String {
+        String::from_utf8(fix_builder.compile_message())
+            .unwrap()
+            .replace(crate::FIX_DELIMITER as char, "|")
+    }
+
+    fn golden(src: &str) -> &str {
+        src.trim_end()
+    }
+
+    #[test]
+    fn test_logon() {
+        let result = super::logon(&settings(), 1, true, DateTimeAsMicroseconds::new(NOW));
+        assert_eq!(compile(result), golden(include_str!("../golden/logon.fix")));
+    }
+
+    #[test]
+    fn test_logon_with_all_options() {
+        let mut settings = settings();
+        settings.username = Some("user".to_string());
+        settings.sender_sub_id = Some("DESK".to_string());
+        settings.target_sub_id = Some("QUOTES".to_string());
+        settings.heartbeat_interval_sec = 15;
+        settings.reset_seq_num_on_logon = false;
+        settings.extra_logon_tags = vec![
+            ("1137".to_string(), "9".to_string()),
+            ("1408".to_string(), "1.0".to_string()),
+        ];
+        settings.validate().unwrap();
+
+        let result = super::logon(&settings, 1, false, DateTimeAsMicroseconds::new(NOW));
+        assert_eq!(
+            compile(result),
+            golden(include_str!("../golden/logon_custom.fix"))
+        );
+    }
+
+    #[test]
+    fn test_ping() {
+        let result = super::ping(&settings(), 2, DateTimeAsMicroseconds::new(NOW));
+        assert_eq!(compile(result), golden(include_str!("../golden/ping.fix")));
+    }
+
+    #[test]
+    fn test_instrument_subscribe() {
+        let contract = InstrumentSubscribeYbTcpContract::new("EURUSD", "S1-1");
+
+        let result = super::instrument_subscribe(
+            &settings(),
+            &contract,
+            3,
+            DateTimeAsMicroseconds::new(NOW),
+        );
+        assert_eq!(
+            compile(result),
+            golden(include_str!("../golden/instrument_subscribe.fix"))
+        );
+    }
+
+    #[test]
+    fn test_instrument_unsubscribe() {
+        let contract = InstrumentSubscribeYbTcpContract::new("EURUSD", "S1-1");
+
+        let result = super::instrument_unsubscribe(
+            &settings(),
+            &contract,
+            5,
+            DateTimeAsMicroseconds::new(NOW),
+        );
+        assert_eq!(
+            compile(result),
+            golden(include_str!("../golden/instrument_unsubscribe.fix"))
+        );
+    }
+
+    #[test]
+    fn test_order_mass_status_request() {
+        let contract = OrderMassStatusRequestYbTcpContract::new("MS-1");
+
+        let result = super::order_mass_status_request(
+            &settings(),
+            &contract,
+            6,
+            DateTimeAsMicroseconds::new(NOW),
+        );
+        assert_eq!(
+            compile(result),
+            golden(include_str!("../golden/order_mass_status.fix"))
+        );
+    }
+
+    #[test]
+    fn test_place_order() {
+        let contract = PlaceOrderYbTcpContract {
+            id: "ORDER-1".to_string(),
+            symbol: "EURUSD".to_string(),
+            side: PlaceOrderYbTcpContractSide::Buy,
+            qty: rust_decimal::Decimal::new(5, 1),
+            expected_price: None,
+        };
+
+        let result = super::place_order_contract(
+            &settings(),
+            &contract,
+            4,
+            DateTimeAsMicroseconds::new(NOW),
+        );
+        assert_eq!(
+            compile(result),
+            golden(include_str!("../golden/place_order.fix"))
+        );
+    }
+
+    #[test]
+    fn test_place_order_with_microseconds_precision() {
+        let mut settings = settings();
+        settings.timestamp_precision = FixTimestampPrecision::Microseconds;
+
+        let contract = PlaceOrderYbTcpContract {
+            id: "ORDER-1".to_string(),
+            symbol: "EURUSD".to_string(),
+            side: PlaceOrderYbTcpContractSide::Buy,
+            qty: rust_decimal::Decimal::new(5, 1),
+            expected_price: None,
+        };
+
+        let now = DateTimeAsMicroseconds::new(NOW + 123);
+        let payload = super::place_order_contract(&settings, &contract, 4, now).compile_message();
+        let reader = FixMessageReader::from_bytes(&payload);
+
+        for tag in ["52", "60"] {
+            let value = reader.get_value(tag).unwrap().unwrap();
+            assert_eq!(value, "20240425-17:28:02.629123");
+            assert_eq!(parse_fix_date(value).unwrap().unix_microseconds, NOW + 123);
+        }
+    }
 }
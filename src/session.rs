@@ -0,0 +1,170 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
+
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+use crate::{YbFixSettings, YbTcpSocketConnection};
+
+#[derive(Debug, Default, Clone, Copy)]
+struct YbFixSessionTimes {
+    last_logon: Option<DateTimeAsMicroseconds>,
+    //End of the scheduled session the last Logon belongs to
+    session_end: Option<DateTimeAsMicroseconds>,
+}
+
+/// FIX session which outlives a single connection: outgoing MsgSeqNum(34),
+/// the time of the last Logon and the connection messages are sent through.
+/// Shared by every connection made from the same YbTcpSate
+pub struct YbFixSession {
+    next_seq_num: AtomicU64,
+    times: Mutex<YbFixSessionTimes>,
+    connection: Mutex<Option<Arc<YbTcpSocketConnection>>>,
+}
+
+impl YbFixSession {
+    pub fn new() -> Self {
+        Self {
+            next_seq_num: AtomicU64::new(1),
+            times: Mutex::new(YbFixSessionTimes::default()),
+            connection: Mutex::new(None),
+        }
+    }
+
+    pub fn get_next_seq_num(&self) -> u64 {
+        self.next_seq_num.fetch_add(1, Ordering::Relaxed)
+    }
+
+    pub fn reset_seq_num(&self) {
+        self.next_seq_num.store(1, Ordering::Relaxed);
+    }
+
+    /// ResetSeqNumFlag(141) of the next Logon. Always set if `reset_seq_num_on_logon` is.
+    /// With a session schedule it's set for the first Logon and once a new session has started
+    pub fn should_reset_seq_num(
+        &self,
+        settings: &YbFixSettings,
+        now: DateTimeAsMicroseconds,
+    ) -> bool {
+        if settings.reset_seq_num_on_logon {
+            return true;
+        }
+
+        let schedule = match settings.session_schedule.as_ref() {
+            Some(schedule) => schedule,
+            None => return false,
+        };
+
+        match self.times.lock().unwrap().last_logon {
+            Some(last_logon) => schedule.should_reset_seq_num(last_logon, now),
+            None => true,
+        }
+    }
+
+    /// Called when Logon is confirmed by YourBourse
+    pub fn report_logon(&self, settings: &YbFixSettings, now: DateTimeAsMicroseconds) {
+        let mut times = self.times.lock().unwrap();
+        times.last_logon = Some(now);
+        times.session_end = settings
+            .session_schedule
+            .as_ref()
+            .and_then(|schedule| schedule.get_session_end(now));
+    }
+
+    pub fn get_last_logon(&self) -> Option<DateTimeAsMicroseconds> {
+        self.times.lock().unwrap().last_logon
+    }
+
+    /// Logout is expected from this moment on
+    pub fn get_session_end(&self) -> Option<DateTimeAsMicroseconds> {
+        self.times.lock().unwrap().session_end
+    }
+
+    pub fn set_connection(&self, connection: Arc<YbTcpSocketConnection>) {
+        *self.connection.lock().unwrap() = Some(connection);
+    }
+
+    /// Newer connection, if it's set already, is kept
+    pub fn clear_connection(&self, connection: &Arc<YbTcpSocketConnection>) {
+        let mut current = self.connection.lock().unwrap();
+
+        if let Some(current_connection) = current.as_ref() {
+            if Arc::ptr_eq(current_connection, connection) {
+                *current = None;
+            }
+        }
+    }
+
+    pub fn get_connection(&self) -> Option<Arc<YbTcpSocketConnection>> {
+        self.connection.lock().unwrap().clone()
+    }
+}
+
+impl Default for YbFixSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::session_schedule::YbSessionSchedule;
+
+    // Mon 2024-04-22T00:00:00Z. London is at UTC+1
+    const MONDAY: i64 = 1713744000000000;
+    const HOUR: i64 = 3_600_000_000;
+
+    fn settings() -> YbFixSettings {
+        let mut settings = crate::tests_support::settings();
+        settings.reset_seq_num_on_logon = false;
+        settings.session_schedule = Some(
+            YbSessionSchedule::from_str("Europe/London;22:05:00;22:00:00;Sun,Mon,Tue,Wed,Thu")
+                .unwrap(),
+        );
+        settings
+    }
+
+    fn at(hours_from_monday: i64) -> DateTimeAsMicroseconds {
+        DateTimeAsMicroseconds::new(MONDAY + hours_from_monday * HOUR)
+    }
+
+    #[test]
+    fn test_resets_seq_num_once_per_scheduled_session() {
+        let settings = settings();
+        let session = YbFixSession::new();
+
+        assert!(session.should_reset_seq_num(&settings, at(1)));
+        session.report_logon(&settings, at(1));
+        assert_eq!(
+            session.get_session_end().unwrap().unix_microseconds,
+            MONDAY + 21 * HOUR
+        );
+
+        // Reconnect within the same session keeps the sequence
+        assert!(!session.should_reset_seq_num(&settings, at(20)));
+
+        // Session of Monday evening has started since
+        assert!(session.should_reset_seq_num(&settings, at(22)));
+    }
+
+    #[test]
+    fn test_keeps_seq_num_across_connections() {
+        let mut settings = settings();
+        settings.session_schedule = None;
+        let session = YbFixSession::new();
+
+        assert_eq!(session.get_next_seq_num(), 1);
+        assert_eq!(session.get_next_seq_num(), 2);
+        assert!(!session.should_reset_seq_num(&settings, at(1)));
+
+        settings.reset_seq_num_on_logon = true;
+        assert!(session.should_reset_seq_num(&settings, at(1)));
+
+        session.reset_seq_num();
+        assert_eq!(session.get_next_seq_num(), 1);
+    }
+}
@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use my_tcp_sockets::SocketEventCallback;
+
+use crate::{
+    logging::YbFixLogContext, subscriptions::restore_session,
+    tcp_serializer::YourBourseFixTcpSerializer, FixMessage, YbIncomingMessage, YbTcpSate,
+    YbTcpSocketConnection,
+};
+
+/// Wraps the callback of the application. Makes the connection the one `YbTcpSate::send`
+/// goes through and restores the session after every Logon: remembered subscriptions
+/// are resent and, if `mass_status_on_logon` is set, the status of all orders is requested.
+/// Every event is passed to `inner` as is
+pub struct YbSessionCallback<TCallback> {
+    inner: Arc<TCallback>,
+    state: YbTcpSate,
+}
+
+impl<TCallback> YbSessionCallback<TCallback> {
+    /// `state` has to be the one connections are created from
+    pub fn new(inner: Arc<TCallback>, state: YbTcpSate) -> Self {
+        Self { inner, state }
+    }
+
+    fn spawn_restore(&self) {
+        let state = self.state.clone();
+
+        let mass_status_req_id = if state.settings.mass_status_on_logon {
+            Some(format!("MS-{}", state.clock.now().unix_microseconds))
+        } else {
+            None
+        };
+
+        // Restore waits for the throttle, so it's not done on the read loop of the connection
+        tokio::spawn(async move {
+            if let Err(err) = restore_session(&state, mass_status_req_id).await {
+                state.logger.write_warning(
+                    "RestoreSession",
+                    err.to_string(),
+                    YbFixLogContext::from_settings(&state.settings),
+                );
+            }
+        });
+    }
+}
+
+#[async_trait::async_trait]
+impl<TCallback> SocketEventCallback<FixMessage, YourBourseFixTcpSerializer, YbTcpSate>
+    for YbSessionCallback<TCallback>
+where
+    TCallback: SocketEventCallback<FixMessage, YourBourseFixTcpSerializer, YbTcpSate>
+        + Send
+        + Sync
+        + 'static,
+{
+    async fn connected(&self, connection: Arc<YbTcpSocketConnection>) {
+        self.state.session.set_connection(connection.clone());
+        self.inner.connected(connection).await;
+    }
+
+    async fn disconnected(&self, connection: Arc<YbTcpSocketConnection>) {
+        self.state.session.clear_connection(&connection);
+        self.inner.disconnected(connection).await;
+    }
+
+    async fn payload(&self, connection: &Arc<YbTcpSocketConnection>, contract: FixMessage) {
+        if let FixMessage::Incoming(YbIncomingMessage::Logon) = &contract {
+            self.spawn_restore();
+        }
+
+        self.inner.payload(connection, contract).await;
+    }
+}
@@ -0,0 +1,215 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Weekday};
+use chrono_tz::Tz;
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+use serde::{Deserialize, Serialize};
+
+/// Trading session of YourBourse: opens every trading day at `start_time` and closes
+/// at `end_time` of the same day, or of the next day if `end_time` is not after `start_time`.
+/// Times are local to `timezone`. Sequence numbers are reset with every new session
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct YbSessionSchedule {
+    pub timezone: Tz,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+    //Days on which a session starts
+    pub trading_days: Vec<Weekday>,
+}
+
+impl YbSessionSchedule {
+    pub fn is_session_open(&self, now: DateTimeAsMicroseconds) -> bool {
+        self.get_current_session(now).is_some()
+    }
+
+    /// Start and end of the session which is open at `now`
+    pub fn get_current_session(
+        &self,
+        now: DateTimeAsMicroseconds,
+    ) -> Option<(DateTimeAsMicroseconds, DateTimeAsMicroseconds)> {
+        let today = self.to_local_date(now);
+
+        // A session lasts 24 hours at most, so it has started either today or yesterday
+        for date in [today, today - Duration::days(1)] {
+            if let Some((start, end)) = self.get_session(date) {
+                if start.unix_microseconds <= now.unix_microseconds
+                    && now.unix_microseconds < end.unix_microseconds
+                {
+                    return Some((start, end));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Logout is expected at this moment
+    pub fn get_session_end(&self, now: DateTimeAsMicroseconds) -> Option<DateTimeAsMicroseconds> {
+        self.get_current_session(now).map(|(_, end)| end)
+    }
+
+    /// First session start strictly after `now`
+    pub fn get_next_session_start(
+        &self,
+        now: DateTimeAsMicroseconds,
+    ) -> Option<DateTimeAsMicroseconds> {
+        let today = self.to_local_date(now);
+
+        for day in -1..=8 {
+            if let Some((start, _)) = self.get_session(today + Duration::days(day)) {
+                if start.unix_microseconds > now.unix_microseconds {
+                    return Some(start);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// True if a new session has started since the last logon
+    pub fn should_reset_seq_num(
+        &self,
+        last_logon: DateTimeAsMicroseconds,
+        now: DateTimeAsMicroseconds,
+    ) -> bool {
+        match self.get_next_session_start(last_logon) {
+            Some(start) => start.unix_microseconds <= now.unix_microseconds,
+            None => false,
+        }
+    }
+
+    fn get_session(
+        &self,
+        date: NaiveDate,
+    ) -> Option<(DateTimeAsMicroseconds, DateTimeAsMicroseconds)> {
+        if !self.trading_days.contains(&date.weekday()) {
+            return None;
+        }
+
+        let end_date = if self.end_time > self.start_time {
+            date
+        } else {
+            date + Duration::days(1)
+        };
+
+        let start = self.to_utc(date, self.start_time)?;
+        let end = self.to_utc(end_date, self.end_time)?;
+
+        Some((start, end))
+    }
+
+    fn to_local_date(&self, now: DateTimeAsMicroseconds) -> NaiveDate {
+        now.to_chrono_utc()
+            .with_timezone(&self.timezone)
+            .date_naive()
+    }
+
+    fn to_utc(&self, date: NaiveDate, time: NaiveTime) -> Option<DateTimeAsMicroseconds> {
+        let local = date.and_time(time);
+
+        // Time which does not exist because of a DST switch is moved an hour forward
+        let result: DateTime<Tz> = self
+            .timezone
+            .from_local_datetime(&local)
+            .earliest()
+            .or_else(|| {
+                self.timezone
+                    .from_local_datetime(&(local + Duration::hours(1)))
+                    .earliest()
+            })?;
+
+        Some(DateTimeAsMicroseconds::new(result.timestamp_micros()))
+    }
+}
+
+/// `timezone;start_time;end_time;days`, e.g. `Europe/London;22:05:00;22:00:00;Sun,Mon,Tue,Wed,Thu`
+impl FromStr for YbSessionSchedule {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = src.split(';').map(|part| part.trim()).collect();
+
+        if parts.len() != 4 {
+            return Err(format!("Invalid session schedule '{}'", src));
+        }
+
+        let timezone = Tz::from_str(parts[0])
+            .map_err(|_| format!("Unknown session schedule timezone '{}'", parts[0]))?;
+
+        let start_time = NaiveTime::from_str(parts[1])
+            .map_err(|_| format!("Invalid session start time '{}'", parts[1]))?;
+
+        let end_time = NaiveTime::from_str(parts[2])
+            .map_err(|_| format!("Invalid session end time '{}'", parts[2]))?;
+
+        let mut trading_days = vec![];
+
+        for day in parts[3].split(',').filter(|day| !day.trim().is_empty()) {
+            let day = Weekday::from_str(day.trim())
+                .map_err(|_| format!("Invalid trading day '{}'", day))?;
+            trading_days.push(day);
+        }
+
+        Ok(Self {
+            timezone,
+            start_time,
+            end_time,
+            trading_days,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Mon 2024-04-22T00:00:00Z. London is at UTC+1
+    const MONDAY: i64 = 1713744000000000;
+    const HOUR: i64 = 3_600_000_000;
+
+    fn schedule() -> YbSessionSchedule {
+        YbSessionSchedule::from_str("Europe/London;22:05:00;22:00:00;Sun,Mon,Tue,Wed,Thu").unwrap()
+    }
+
+    fn at(hours_from_monday: i64) -> DateTimeAsMicroseconds {
+        DateTimeAsMicroseconds::new(MONDAY + hours_from_monday * HOUR)
+    }
+
+    #[test]
+    fn test_session_crosses_midnight() {
+        let schedule = schedule();
+
+        // Mon 12:00 UTC belongs to the session opened on Sun 21:05 UTC
+        let (start, end) = schedule.get_current_session(at(12)).unwrap();
+        assert_eq!(start.unix_microseconds, MONDAY - 3 * HOUR + HOUR / 12);
+        assert_eq!(end.unix_microseconds, MONDAY + 21 * HOUR);
+
+        // Daily break between 21:00 and 21:05 UTC
+        let in_break = DateTimeAsMicroseconds::new(MONDAY + 21 * HOUR + HOUR / 60);
+        assert!(!schedule.is_session_open(in_break));
+    }
+
+    #[test]
+    fn test_closed_over_the_weekend() {
+        let schedule = schedule();
+
+        // Sat 2024-04-27 12:00 UTC
+        let saturday = at(5 * 24 + 12);
+        assert!(!schedule.is_session_open(saturday));
+
+        // Opens on Sun 2024-04-28 21:05 UTC
+        let next_start = schedule.get_next_session_start(saturday).unwrap();
+        assert_eq!(
+            next_start.unix_microseconds,
+            MONDAY + (6 * 24 + 21) * HOUR + HOUR / 12
+        );
+    }
+
+    #[test]
+    fn test_should_reset_seq_num() {
+        let schedule = schedule();
+
+        assert!(!schedule.should_reset_seq_num(at(1), at(20)));
+        assert!(schedule.should_reset_seq_num(at(20), at(22)));
+    }
+}
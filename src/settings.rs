@@ -0,0 +1,805 @@
+use std::{fmt::Debug, path::Path};
+
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cl_ord_id::DEFAULT_MAX_CL_ORD_ID_LEN, date_utils::FixTimestampPrecision, risk::YbRiskLimits,
+    session_schedule::YbSessionSchedule, throttle::YbRateLimit, FixMessagePrinter,
+    DEFAULT_MASKED_TAGS,
+};
+
+pub const DEFAULT_HEARTBEAT_INTERVAL_SEC: u32 = 30;
+pub const DEFAULT_READ_BUFFER_SIZE: usize = 2048 * 24;
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+pub const DEFAULT_ENV_PREFIX: &str = "YB_FIX";
+pub const DEFAULT_RECONNECT_BACKOFF_INITIAL_MS: u64 = 1000;
+pub const DEFAULT_RECONNECT_BACKOFF_MAX_MS: u64 = 60_000;
+
+//Header and trailer fields and the Logon fields the crate fills itself
+const RESERVED_LOGON_TAGS: [&str; 18] = [
+    "8", "9", "10", "34", "35", "43", "49", "50", "52", "56", "57", "97", "122", "98", "108",
+    "141", "553", "554",
+];
+
+/// TLS of the connection to the gateway. Requires the `tls` feature
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct YbFixTlsSettings {
+    //PEM file with trusted CA certificates. Mozilla root certificates are used if not set
+    #[serde(default)]
+    pub ca_bundle_path: Option<String>,
+    //PEM files of the client certificate chain and its private key
+    #[serde(default)]
+    pub client_cert_path: Option<String>,
+    #[serde(default)]
+    pub client_key_path: Option<String>,
+    //SNI and the name the gateway certificate is checked against. Host of the url if not set
+    #[serde(default)]
+    pub server_name: Option<String>,
+}
+
+/// Gateway to connect to. Endpoints with lower priority are tried first
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct YbFixEndpoint {
+    pub url: String,
+    #[serde(default)]
+    pub priority: u32,
+}
+
+/// Can be a field of the service settings model read by service-sdk.
+/// Every field except url, password and comp IDs has a default
+#[derive(Clone, Serialize, Deserialize)]
+pub struct YbFixSettings {
+    pub url: String,
+    pub password: String,
+    pub sender_company_id: String,
+    pub target_company_id: String,
+    //Username(553). Sent only if set
+    #[serde(default)]
+    pub username: Option<String>,
+    //SenderSubID(50). Sent in the header of every message if set
+    #[serde(default)]
+    pub sender_sub_id: Option<String>,
+    //TargetSubID(57). Sent in the header of every message if set
+    #[serde(default)]
+    pub target_sub_id: Option<String>,
+    //HeartBtInt(108)
+    #[serde(default = "default_heartbeat_interval_sec")]
+    pub heartbeat_interval_sec: u32,
+    //ResetSeqNumFlag(141) of every Logon. If not set, sequence numbers are kept across
+    //reconnects and reset only with a new session of session_schedule
+    #[serde(default = "default_reset_seq_num_on_logon")]
+    pub reset_seq_num_on_logon: bool,
+    //OrderMassStatusRequest(AF) is sent after every Logon to reconcile orders changed while
+    //disconnected. Requires YbSessionCallback
+    #[serde(default)]
+    pub mass_status_on_logon: bool,
+    //Tags appended to the Logon message as is, in the order they are declared.
+    //Header, trailer and the Logon fields generated by the crate can not be set here
+    #[serde(default)]
+    pub extra_logon_tags: Vec<(String, String)>,
+    //Tags which values are replaced with *** when a message is rendered for logs
+    #[serde(default = "default_masked_tags")]
+    pub masked_tags: Vec<String>,
+    //Initial state of raw message tracing. Can be switched at runtime through YbFixLogger
+    #[serde(default)]
+    pub raw_trace: bool,
+    //If set, every inbound and outbound message is written to a rolling journal in this folder.
+    //Files are named `{sender_company_id}-{target_company_id}-{YYYYMMDD}-{index}.fixlog`
+    #[serde(default)]
+    pub journal_folder: Option<String>,
+    //Size of the socket read buffer. Messages bigger than the buffer are assembled field by field,
+    //so a single field longer than the buffer can not be received and closes the connection
+    #[serde(default = "default_read_buffer_size")]
+    pub read_buffer_size: usize,
+    //Connection is closed if BodyLength(9) announces a message bigger than this
+    #[serde(default = "default_max_message_size")]
+    pub max_message_size: usize,
+    //PlaceOrder with a longer ClOrdID(11) is not sent
+    #[serde(default = "default_max_cl_ord_id_len")]
+    pub max_cl_ord_id_len: usize,
+    //Precision of SendingTime(52) and TransactTime(60) of outbound messages
+    #[serde(default)]
+    pub timestamp_precision: FixTimestampPrecision,
+    //If set, Logon is not sent while the session is closed. Connecting is postponed until it
+    //opens only by YbEndpointSelector. Nothing is closed at the end: YourBourse logs us out.
+    //Not set means open 24/7
+    #[serde(default)]
+    pub session_schedule: Option<YbSessionSchedule>,
+    //Gateways tried when url is not available. url itself has priority 0
+    #[serde(default)]
+    pub backup_endpoints: Vec<YbFixEndpoint>,
+    //Delay before the next round over all endpoints. Doubled after every failed round
+    #[serde(default = "default_reconnect_backoff_initial_ms")]
+    pub reconnect_backoff_initial_ms: u64,
+    #[serde(default = "default_reconnect_backoff_max_ms")]
+    pub reconnect_backoff_max_ms: u64,
+    //If set, the gateway is connected over TLS through YbTlsTunnel
+    #[serde(default)]
+    pub tls: Option<YbFixTlsSettings>,
+    //Limits of PlaceOrder and OrderMassStatusRequest. Not limited if not set
+    #[serde(default)]
+    pub order_rate_limit: Option<YbRateLimit>,
+    //Limits of market data subscribe and unsubscribe requests. Not limited if not set
+    #[serde(default)]
+    pub market_data_rate_limit: Option<YbRateLimit>,
+    //If set, PlaceOrder is checked against the limits before it is sent
+    #[serde(default)]
+    pub risk_limits: Option<YbRiskLimits>,
+}
+
+impl YbFixSettings {
+    pub fn new(
+        url: String,
+        password: String,
+        sender_company_id: String,
+        target_company_id: String,
+    ) -> Self {
+        Self {
+            url,
+            password,
+            sender_company_id,
+            target_company_id,
+            username: None,
+            sender_sub_id: None,
+            target_sub_id: None,
+            heartbeat_interval_sec: DEFAULT_HEARTBEAT_INTERVAL_SEC,
+            reset_seq_num_on_logon: true,
+            mass_status_on_logon: false,
+            extra_logon_tags: vec![],
+            masked_tags: default_masked_tags(),
+            raw_trace: false,
+            journal_folder: None,
+            read_buffer_size: DEFAULT_READ_BUFFER_SIZE,
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            max_cl_ord_id_len: DEFAULT_MAX_CL_ORD_ID_LEN,
+            timestamp_precision: FixTimestampPrecision::default(),
+            session_schedule: None,
+            backup_endpoints: vec![],
+            reconnect_backoff_initial_ms: DEFAULT_RECONNECT_BACKOFF_INITIAL_MS,
+            reconnect_backoff_max_ms: DEFAULT_RECONNECT_BACKOFF_MAX_MS,
+            tls: None,
+            order_rate_limit: None,
+            market_data_rate_limit: None,
+            risk_limits: None,
+        }
+    }
+
+    /// Reads settings from environment variables named `{prefix}_URL`, `{prefix}_PASSWORD`,
+    /// `{prefix}_SENDER_COMPANY_ID`, `{prefix}_TARGET_COMPANY_ID`, `{prefix}_USERNAME`,
+    /// `{prefix}_SENDER_SUB_ID`, `{prefix}_TARGET_SUB_ID`, `{prefix}_HEARTBEAT_INTERVAL_SEC`,
+    /// `{prefix}_RESET_SEQ_NUM_ON_LOGON`, `{prefix}_MASS_STATUS_ON_LOGON`,
+    /// `{prefix}_EXTRA_LOGON_TAGS` (`tag=value;tag=value`)
+    /// `{prefix}_MASKED_TAGS` (`tag,tag`), `{prefix}_RAW_TRACE`, `{prefix}_JOURNAL_FOLDER`,
+    /// `{prefix}_READ_BUFFER_SIZE`, `{prefix}_MAX_MESSAGE_SIZE`, `{prefix}_MAX_CL_ORD_ID_LEN`
+    /// `{prefix}_TIMESTAMP_PRECISION` (`seconds`, `milliseconds` or `microseconds`),
+    /// `{prefix}_SESSION_SCHEDULE` (`Europe/London;22:05:00;22:00:00;Sun,Mon,Tue,Wed,Thu`),
+    /// `{prefix}_BACKUP_URLS` (`host:port,host:port` in the order of priority),
+    /// `{prefix}_RECONNECT_BACKOFF_INITIAL_MS`, `{prefix}_RECONNECT_BACKOFF_MAX_MS`,
+    /// `{prefix}_TLS` (`true` to enable TLS), `{prefix}_TLS_CA_BUNDLE_PATH`,
+    /// `{prefix}_TLS_CLIENT_CERT_PATH`, `{prefix}_TLS_CLIENT_KEY_PATH`, `{prefix}_TLS_SERVER_NAME`,
+    /// `{prefix}_ORDER_RATE_LIMIT`, `{prefix}_MARKET_DATA_RATE_LIMIT` (`messages_per_sec,burst`),
+    /// `{prefix}_RISK_ALLOWED_SYMBOLS` (`symbol,symbol`), `{prefix}_RISK_MAX_ORDER_QTY`,
+    /// `{prefix}_RISK_MAX_NOTIONAL`, `{prefix}_RISK_MAX_OPEN_ORDERS`
+    /// and `{prefix}_RISK_MAX_PRICE_DEVIATION_PERCENT`
+    pub fn from_env(prefix: &str) -> Result<Self, YbFixSettingsError> {
+        let mut result = Self::new(
+            read_env_required(prefix, "URL")?,
+            read_env_required(prefix, "PASSWORD")?,
+            read_env_required(prefix, "SENDER_COMPANY_ID")?,
+            read_env_required(prefix, "TARGET_COMPANY_ID")?,
+        );
+
+        result.username = read_env(prefix, "USERNAME");
+        result.sender_sub_id = read_env(prefix, "SENDER_SUB_ID");
+        result.target_sub_id = read_env(prefix, "TARGET_SUB_ID");
+
+        if let Some(value) = read_env(prefix, "HEARTBEAT_INTERVAL_SEC") {
+            result.heartbeat_interval_sec = parse_value("HEARTBEAT_INTERVAL_SEC", &value)?;
+        }
+
+        if let Some(value) = read_env(prefix, "RESET_SEQ_NUM_ON_LOGON") {
+            result.reset_seq_num_on_logon = parse_value("RESET_SEQ_NUM_ON_LOGON", &value)?;
+        }
+
+        if let Some(value) = read_env(prefix, "MASS_STATUS_ON_LOGON") {
+            result.mass_status_on_logon = parse_value("MASS_STATUS_ON_LOGON", &value)?;
+        }
+
+        if let Some(value) = read_env(prefix, "EXTRA_LOGON_TAGS") {
+            result.extra_logon_tags = parse_extra_logon_tags(&value)?;
+        }
+
+        result.journal_folder = read_env(prefix, "JOURNAL_FOLDER");
+
+        if let Some(value) = read_env(prefix, "READ_BUFFER_SIZE") {
+            result.read_buffer_size = parse_value("READ_BUFFER_SIZE", &value)?;
+        }
+
+        if let Some(value) = read_env(prefix, "MAX_MESSAGE_SIZE") {
+            result.max_message_size = parse_value("MAX_MESSAGE_SIZE", &value)?;
+        }
+
+        if let Some(value) = read_env(prefix, "MAX_CL_ORD_ID_LEN") {
+            result.max_cl_ord_id_len = parse_value("MAX_CL_ORD_ID_LEN", &value)?;
+        }
+
+        if let Some(value) = read_env(prefix, "TIMESTAMP_PRECISION") {
+            result.timestamp_precision = parse_value("TIMESTAMP_PRECISION", &value)?;
+        }
+
+        if let Some(value) = read_env(prefix, "SESSION_SCHEDULE") {
+            result.session_schedule = Some(parse_value("SESSION_SCHEDULE", &value)?);
+        }
+
+        if let Some(value) = read_env(prefix, "BACKUP_URLS") {
+            result.backup_endpoints = value
+                .split(',')
+                .map(|url| url.trim())
+                .filter(|url| !url.is_empty())
+                .enumerate()
+                .map(|(index, url)| YbFixEndpoint {
+                    url: url.to_string(),
+                    priority: index as u32 + 1,
+                })
+                .collect();
+        }
+
+        if let Some(value) = read_env(prefix, "RECONNECT_BACKOFF_INITIAL_MS") {
+            result.reconnect_backoff_initial_ms =
+                parse_value("RECONNECT_BACKOFF_INITIAL_MS", &value)?;
+        }
+
+        if let Some(value) = read_env(prefix, "RECONNECT_BACKOFF_MAX_MS") {
+            result.reconnect_backoff_max_ms = parse_value("RECONNECT_BACKOFF_MAX_MS", &value)?;
+        }
+
+        if let Some(value) = read_env(prefix, "TLS") {
+            if parse_value::<bool>("TLS", &value)? {
+                result.tls = Some(YbFixTlsSettings {
+                    ca_bundle_path: read_env(prefix, "TLS_CA_BUNDLE_PATH"),
+                    client_cert_path: read_env(prefix, "TLS_CLIENT_CERT_PATH"),
+                    client_key_path: read_env(prefix, "TLS_CLIENT_KEY_PATH"),
+                    server_name: read_env(prefix, "TLS_SERVER_NAME"),
+                });
+            }
+        }
+
+        if let Some(value) = read_env(prefix, "ORDER_RATE_LIMIT") {
+            result.order_rate_limit = Some(parse_value("ORDER_RATE_LIMIT", &value)?);
+        }
+
+        if let Some(value) = read_env(prefix, "MARKET_DATA_RATE_LIMIT") {
+            result.market_data_rate_limit = Some(parse_value("MARKET_DATA_RATE_LIMIT", &value)?);
+        }
+
+        let mut risk_limits = YbRiskLimits::default();
+
+        if let Some(value) = read_env(prefix, "RISK_ALLOWED_SYMBOLS") {
+            risk_limits.allowed_symbols = Some(
+                value
+                    .split(',')
+                    .map(|symbol| symbol.trim())
+                    .filter(|symbol| !symbol.is_empty())
+                    .map(|symbol| symbol.to_string())
+                    .collect(),
+            );
+        }
+
+        if let Some(value) = read_env(prefix, "RISK_MAX_ORDER_QTY") {
+            risk_limits.max_order_qty = Some(parse_value("RISK_MAX_ORDER_QTY", &value)?);
+        }
+
+        if let Some(value) = read_env(prefix, "RISK_MAX_NOTIONAL") {
+            risk_limits.max_notional = Some(parse_value("RISK_MAX_NOTIONAL", &value)?);
+        }
+
+        if let Some(value) = read_env(prefix, "RISK_MAX_OPEN_ORDERS") {
+            risk_limits.max_open_orders = Some(parse_value("RISK_MAX_OPEN_ORDERS", &value)?);
+        }
+
+        if let Some(value) = read_env(prefix, "RISK_MAX_PRICE_DEVIATION_PERCENT") {
+            risk_limits.max_price_deviation_percent =
+                Some(parse_value("RISK_MAX_PRICE_DEVIATION_PERCENT", &value)?);
+        }
+
+        if risk_limits != YbRiskLimits::default() {
+            result.risk_limits = Some(risk_limits);
+        }
+
+        if let Some(value) = read_env(prefix, "RAW_TRACE") {
+            result.raw_trace = parse_value("RAW_TRACE", &value)?;
+        }
+
+        if let Some(value) = read_env(prefix, "MASKED_TAGS") {
+            result.masked_tags = value
+                .split(',')
+                .map(|tag| tag.trim())
+                .filter(|tag| !tag.is_empty())
+                .map(|tag| tag.to_string())
+                .collect();
+        }
+
+        result.validate()?;
+        Ok(result)
+    }
+
+    pub fn from_toml(src: &str) -> Result<Self, YbFixSettingsError> {
+        let result: Self =
+            toml::from_str(src).map_err(|err| YbFixSettingsError::Parse(err.to_string()))?;
+        result.validate()?;
+        Ok(result)
+    }
+
+    pub fn from_yaml(src: &str) -> Result<Self, YbFixSettingsError> {
+        let result: Self =
+            serde_yaml::from_str(src).map_err(|err| YbFixSettingsError::Parse(err.to_string()))?;
+        result.validate()?;
+        Ok(result)
+    }
+
+    /// Format is picked by file extension: `.toml`, `.yaml` or `.yml`
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, YbFixSettingsError> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|err| YbFixSettingsError::Io(format!("{}: {}", path.display(), err)))?;
+
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Self::from_toml(&content),
+            Some("yaml") | Some("yml") => Self::from_yaml(&content),
+            _ => Err(YbFixSettingsError::Io(format!(
+                "{}: unsupported settings file extension",
+                path.display()
+            ))),
+        }
+    }
+
+    /// For settings embedded into the service settings model, e.g.
+    /// `settings_reader.get(|x| x.yb_fix.clone()).await.validated()?`
+    pub fn validated(self) -> Result<Self, YbFixSettingsError> {
+        self.validate()?;
+        Ok(self)
+    }
+
+    pub fn validate(&self) -> Result<(), YbFixSettingsError> {
+        if self.sender_company_id.trim().is_empty() {
+            return Err(YbFixSettingsError::MissingValue("sender_company_id"));
+        }
+
+        if self.target_company_id.trim().is_empty() {
+            return Err(YbFixSettingsError::MissingValue("target_company_id"));
+        }
+
+        if self.heartbeat_interval_sec == 0 {
+            return Err(YbFixSettingsError::InvalidValue {
+                name: "heartbeat_interval_sec",
+                value: self.heartbeat_interval_sec.to_string(),
+            });
+        }
+
+        for (tag, value) in self.extra_logon_tags.iter() {
+            if tag.parse::<u32>().is_err()
+                || RESERVED_LOGON_TAGS.contains(&tag.as_str())
+                || value.is_empty()
+                || value.as_bytes().contains(&crate::FIX_DELIMITER)
+            {
+                return Err(YbFixSettingsError::InvalidValue {
+                    name: "extra_logon_tags",
+                    value: tag.to_string(),
+                });
+            }
+        }
+
+        if self.read_buffer_size < 1024 {
+            return Err(YbFixSettingsError::InvalidValue {
+                name: "read_buffer_size",
+                value: self.read_buffer_size.to_string(),
+            });
+        }
+
+        if self.max_message_size < self.read_buffer_size {
+            return Err(YbFixSettingsError::InvalidValue {
+                name: "max_message_size",
+                value: self.max_message_size.to_string(),
+            });
+        }
+
+        if let Some(schedule) = self.session_schedule.as_ref() {
+            if schedule.trading_days.is_empty() {
+                return Err(YbFixSettingsError::MissingValue(
+                    "session_schedule.trading_days",
+                ));
+            }
+        }
+
+        if self.reconnect_backoff_max_ms < self.reconnect_backoff_initial_ms {
+            return Err(YbFixSettingsError::InvalidValue {
+                name: "reconnect_backoff_max_ms",
+                value: self.reconnect_backoff_max_ms.to_string(),
+            });
+        }
+
+        if let Some(tls) = self.tls.as_ref() {
+            if !cfg!(feature = "tls") {
+                return Err(YbFixSettingsError::FeatureNotEnabled {
+                    name: "tls",
+                    feature: "tls",
+                });
+            }
+
+            if tls.client_cert_path.is_some() != tls.client_key_path.is_some() {
+                return Err(YbFixSettingsError::MissingValue(
+                    "tls.client_cert_path and tls.client_key_path",
+                ));
+            }
+        }
+
+        for (name, rate_limit) in [
+            ("order_rate_limit", self.order_rate_limit.as_ref()),
+            (
+                "market_data_rate_limit",
+                self.market_data_rate_limit.as_ref(),
+            ),
+        ] {
+            if let Some(rate_limit) = rate_limit {
+                if rate_limit.messages_per_sec.is_nan()
+                    || rate_limit.messages_per_sec <= 0.0
+                    || rate_limit.burst == 0
+                {
+                    return Err(YbFixSettingsError::InvalidValue {
+                        name,
+                        value: format!("{:?}", rate_limit),
+                    });
+                }
+            }
+        }
+
+        for endpoint in self.get_endpoints() {
+            parse_host_port(&endpoint.url)?;
+        }
+
+        Ok(())
+    }
+
+    /// `url` and backup endpoints ordered by priority
+    pub fn get_endpoints(&self) -> Vec<YbFixEndpoint> {
+        let mut result = vec![YbFixEndpoint {
+            url: self.url.clone(),
+            priority: 0,
+        }];
+
+        result.extend(self.backup_endpoints.iter().cloned());
+        result.sort_by_key(|endpoint| endpoint.priority);
+        result
+    }
+
+    pub fn is_session_open(&self, now: DateTimeAsMicroseconds) -> bool {
+        match self.session_schedule.as_ref() {
+            Some(schedule) => schedule.is_session_open(now),
+            None => true,
+        }
+    }
+
+    pub fn get_fix_printer(&self) -> FixMessagePrinter {
+        FixMessagePrinter::new(self.masked_tags.clone())
+    }
+
+    pub fn get_host_port(&self) -> Result<(&str, u16), YbFixSettingsError> {
+        parse_host_port(&self.url)
+    }
+}
+
+impl Debug for YbFixSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let extra_logon_tags: Vec<(&str, &str)> = self
+            .extra_logon_tags
+            .iter()
+            .map(|(tag, value)| {
+                if self.masked_tags.contains(tag) {
+                    (tag.as_str(), "***")
+                } else {
+                    (tag.as_str(), value.as_str())
+                }
+            })
+            .collect();
+
+        f.debug_struct("YbFixSettings")
+            .field("url", &self.url)
+            .field("password", &"***")
+            .field("sender_company_id", &self.sender_company_id)
+            .field("target_company_id", &self.target_company_id)
+            .field("username", &self.username)
+            .field("sender_sub_id", &self.sender_sub_id)
+            .field("target_sub_id", &self.target_sub_id)
+            .field("heartbeat_interval_sec", &self.heartbeat_interval_sec)
+            .field("reset_seq_num_on_logon", &self.reset_seq_num_on_logon)
+            .field("mass_status_on_logon", &self.mass_status_on_logon)
+            .field("extra_logon_tags", &extra_logon_tags)
+            .field("masked_tags", &self.masked_tags)
+            .field("raw_trace", &self.raw_trace)
+            .field("journal_folder", &self.journal_folder)
+            .field("read_buffer_size", &self.read_buffer_size)
+            .field("max_message_size", &self.max_message_size)
+            .field("max_cl_ord_id_len", &self.max_cl_ord_id_len)
+            .field("timestamp_precision", &self.timestamp_precision)
+            .field("session_schedule", &self.session_schedule)
+            .field("backup_endpoints", &self.backup_endpoints)
+            .field(
+                "reconnect_backoff_initial_ms",
+                &self.reconnect_backoff_initial_ms,
+            )
+            .field("reconnect_backoff_max_ms", &self.reconnect_backoff_max_ms)
+            .field("tls", &self.tls)
+            .field("order_rate_limit", &self.order_rate_limit)
+            .field("market_data_rate_limit", &self.market_data_rate_limit)
+            .field("risk_limits", &self.risk_limits)
+            .finish()
+    }
+}
+
+#[derive(Debug)]
+pub enum YbFixSettingsError {
+    MissingValue(&'static str),
+    InvalidValue {
+        name: &'static str,
+        value: String,
+    },
+    InvalidUrl(String),
+    Io(String),
+    Parse(String),
+    FeatureNotEnabled {
+        name: &'static str,
+        feature: &'static str,
+    },
+}
+
+impl std::fmt::Display for YbFixSettingsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingValue(name) => write!(f, "Setting '{}' is missing or empty", name),
+            Self::InvalidValue { name, value } => {
+                write!(f, "Setting '{}' has invalid value '{}'", name, value)
+            }
+            Self::InvalidUrl(url) => write!(f, "Url '{}' is not a valid host:port", url),
+            Self::Io(err) => write!(f, "Can not read settings: {}", err),
+            Self::Parse(err) => write!(f, "Can not parse settings: {}", err),
+            Self::FeatureNotEnabled { name, feature } => write!(
+                f,
+                "Setting '{}' requires the crate to be built with the '{}' feature",
+                name, feature
+            ),
+        }
+    }
+}
+
+impl std::error::Error for YbFixSettingsError {}
+
+fn default_heartbeat_interval_sec() -> u32 {
+    DEFAULT_HEARTBEAT_INTERVAL_SEC
+}
+
+fn default_reset_seq_num_on_logon() -> bool {
+    true
+}
+
+fn default_read_buffer_size() -> usize {
+    DEFAULT_READ_BUFFER_SIZE
+}
+
+fn default_max_message_size() -> usize {
+    DEFAULT_MAX_MESSAGE_SIZE
+}
+
+fn default_max_cl_ord_id_len() -> usize {
+    DEFAULT_MAX_CL_ORD_ID_LEN
+}
+
+fn default_reconnect_backoff_initial_ms() -> u64 {
+    DEFAULT_RECONNECT_BACKOFF_INITIAL_MS
+}
+
+fn default_reconnect_backoff_max_ms() -> u64 {
+    DEFAULT_RECONNECT_BACKOFF_MAX_MS
+}
+
+fn default_masked_tags() -> Vec<String> {
+    DEFAULT_MASKED_TAGS
+        .iter()
+        .map(|tag| tag.to_string())
+        .collect()
+}
+
+fn read_env(prefix: &str, name: &str) -> Option<String> {
+    std::env::var(format!("{}_{}", prefix, name)).ok()
+}
+
+fn read_env_required(prefix: &str, name: &'static str) -> Result<String, YbFixSettingsError> {
+    match read_env(prefix, name) {
+        Some(value) => Ok(value),
+        None => Err(YbFixSettingsError::MissingValue(name)),
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(
+    name: &'static str,
+    value: &str,
+) -> Result<T, YbFixSettingsError> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| YbFixSettingsError::InvalidValue {
+            name,
+            value: value.to_string(),
+        })
+}
+
+fn parse_extra_logon_tags(src: &str) -> Result<Vec<(String, String)>, YbFixSettingsError> {
+    let mut result = vec![];
+
+    for pair in src.split(';').filter(|pair| !pair.trim().is_empty()) {
+        match pair.split_once('=') {
+            Some((tag, value)) if tag.trim().parse::<u32>().is_ok() => {
+                result.push((tag.trim().to_string(), value.to_string()));
+            }
+            _ => {
+                return Err(YbFixSettingsError::InvalidValue {
+                    name: "EXTRA_LOGON_TAGS",
+                    value: pair.to_string(),
+                })
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+pub(crate) fn parse_host_port(url: &str) -> Result<(&str, u16), YbFixSettingsError> {
+    let (host, port) = match url.trim().rsplit_once(':') {
+        Some(result) => result,
+        None => return Err(YbFixSettingsError::InvalidUrl(url.to_string())),
+    };
+
+    if host.is_empty() || host.contains('/') {
+        return Err(YbFixSettingsError::InvalidUrl(url.to_string()));
+    }
+
+    match port.parse::<u16>() {
+        Ok(port) if port > 0 => Ok((host, port)),
+        _ => Err(YbFixSettingsError::InvalidUrl(url.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_host_port() {
+        assert_eq!(
+            parse_host_port("10.0.0.1:5001").unwrap(),
+            ("10.0.0.1", 5001)
+        );
+        assert!(parse_host_port("10.0.0.1").is_err());
+        assert!(parse_host_port(":5001").is_err());
+        assert!(parse_host_port("tcp://10.0.0.1:5001").is_err());
+        assert!(parse_host_port("10.0.0.1:port").is_err());
+    }
+
+    #[test]
+    fn test_from_toml_applies_defaults() {
+        let settings = YbFixSettings::from_toml(
+            r#"
+            url = "fix.yourbourse.com:5001"
+            password = "secret"
+            sender_company_id = "SENDER"
+            target_company_id = "TARGET"
+            extra_logon_tags = [["1137", "9"]]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            settings.heartbeat_interval_sec,
+            DEFAULT_HEARTBEAT_INTERVAL_SEC
+        );
+        assert!(settings.reset_seq_num_on_logon);
+        assert_eq!(
+            settings.extra_logon_tags,
+            vec![("1137".to_string(), "9".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_embedded_into_service_settings() {
+        #[derive(Serialize, Deserialize)]
+        struct ServiceSettingsModel {
+            seq_conn_string: String,
+            yb_fix: YbFixSettings,
+        }
+
+        let model: ServiceSettingsModel = serde_yaml::from_str(
+            r#"
+            seq_conn_string: http://seq:5341
+            yb_fix:
+              url: fix.yourbourse.com:5001
+              password: secret
+              sender_company_id: SENDER
+              target_company_id: TARGET
+              session_schedule:
+                timezone: Europe/London
+                start_time: "22:05:00"
+                end_time: "22:00:00"
+                trading_days: [Sun, Mon, Tue, Wed, Thu]
+            "#,
+        )
+        .unwrap();
+
+        let settings = model.yb_fix.clone().validated().unwrap();
+        assert_eq!(settings.sender_company_id, "SENDER");
+        assert!(settings.session_schedule.is_some());
+
+        let round_trip: ServiceSettingsModel =
+            serde_yaml::from_str(&serde_yaml::to_string(&model).unwrap()).unwrap();
+        assert_eq!(
+            round_trip.yb_fix.session_schedule,
+            settings.session_schedule
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_empty_comp_id() {
+        let mut settings = crate::tests_support::settings();
+        settings.sender_company_id = "".to_string();
+
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_rejects_reserved_and_malformed_extra_logon_tags() {
+        let mut settings = crate::tests_support::settings();
+
+        for (tag, value) in [
+            ("49", "SPOOFED"),
+            ("554", "other"),
+            ("10", "000"),
+            ("141", "N"),
+            ("1137", "9\x0135=D"),
+            ("1137", ""),
+            ("tag", "9"),
+        ] {
+            settings.extra_logon_tags = vec![(tag.to_string(), value.to_string())];
+            assert!(settings.validate().is_err(), "{}={}", tag, value);
+        }
+
+        settings.extra_logon_tags = vec![("1137".to_string(), "9".to_string())];
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_tls_requires_feature() {
+        let mut settings = crate::tests_support::settings();
+
+        settings.tls = Some(YbFixTlsSettings::default());
+
+        if cfg!(feature = "tls") {
+            assert!(settings.validate().is_ok());
+        } else {
+            assert!(matches!(
+                settings.validate(),
+                Err(YbFixSettingsError::FeatureNotEnabled { name: "tls", .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_debug_does_not_print_password() {
+        let mut settings = crate::tests_support::settings();
+
+        //NewPassword
+        settings.extra_logon_tags = vec![("925".to_string(), "new-secret".to_string())];
+
+        let result = format!("{:?}", settings);
+        assert!(!result.contains("secret"));
+        assert!(result.contains("925"));
+    }
+}
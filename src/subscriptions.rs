@@ -0,0 +1,96 @@
+use std::sync::Mutex;
+
+use crate::{
+    tcp_messages::{InstrumentSubscribeYbTcpContract, OrderMassStatusRequestYbTcpContract},
+    YbOutgoingMessage, YbSendError, YbTcpSate,
+};
+
+/// Market data subscriptions sent through the session. Resent by `restore_session`
+pub struct YbSubscriptionRegistry {
+    items: Mutex<Vec<InstrumentSubscribeYbTcpContract>>,
+}
+
+impl YbSubscriptionRegistry {
+    pub fn new() -> Self {
+        Self {
+            items: Mutex::new(vec![]),
+        }
+    }
+
+    /// Subscription with the same MDReqID(262) is replaced
+    pub fn add(&self, contract: &InstrumentSubscribeYbTcpContract) {
+        let mut items = self.items.lock().unwrap();
+        items.retain(|item| item.md_req_id != contract.md_req_id);
+        items.push(contract.clone());
+    }
+
+    pub fn remove(&self, md_req_id: &str) -> Option<InstrumentSubscribeYbTcpContract> {
+        let mut items = self.items.lock().unwrap();
+        let index = items.iter().position(|item| item.md_req_id == md_req_id)?;
+        Some(items.remove(index))
+    }
+
+    pub fn get_all(&self) -> Vec<InstrumentSubscribeYbTcpContract> {
+        self.items.lock().unwrap().clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.lock().unwrap().is_empty()
+    }
+}
+
+impl Default for YbSubscriptionRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Called by YbSessionCallback once YbIncomingMessage::Logon is received on a new connection.
+/// Call it yourself only if connection events are not handled through YbSessionCallback.
+/// Resends every remembered subscription and, if `mass_status_req_id` is set,
+/// requests the status of all orders to reconcile the ones changed while disconnected
+pub async fn restore_session(
+    state: &YbTcpSate,
+    mass_status_req_id: Option<String>,
+) -> Result<(), YbSendError> {
+    for contract in state.subscriptions.get_all() {
+        let message = YbOutgoingMessage::InstrumentSubscribe(contract);
+        state.send(message).await?;
+    }
+
+    if let Some(mass_status_req_id) = mass_status_req_id {
+        let contract = OrderMassStatusRequestYbTcpContract::new(mass_status_req_id);
+        let message = YbOutgoingMessage::OrderMassStatusRequest(contract);
+        state.send(message).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_keeps_one_subscription_per_md_req_id() {
+        let registry = YbSubscriptionRegistry::new();
+
+        registry.add(&InstrumentSubscribeYbTcpContract::new("EURUSD", "S1-1"));
+        registry.add(&InstrumentSubscribeYbTcpContract::new("GBPUSD", "S1-2"));
+        registry.add(&InstrumentSubscribeYbTcpContract::new("EURUSD", "S1-1"));
+        assert_eq!(registry.len(), 2);
+
+        assert!(!registry.is_empty());
+
+        let removed = registry.remove("S1-1").unwrap();
+        assert_eq!(removed.symbol, "EURUSD");
+        assert!(registry.remove("S1-1").is_none());
+
+        let symbols: Vec<String> = registry.get_all().into_iter().map(|x| x.symbol).collect();
+        assert_eq!(symbols, vec!["GBPUSD".to_string()]);
+    }
+}
@@ -1,73 +1,177 @@
+use std::str::FromStr;
+
 use my_tcp_sockets::TcpContract;
+use rust_decimal::Decimal;
 use rust_extensions::date_time::DateTimeAsMicroseconds;
 use rust_fix::{FixMessageReader, FixSerializeError};
 
+use crate::{fix_decimal::parse_fix_decimal, md_req_id::MdReqIdGenerator, FixMessagePrinter};
+
+/// Contract of YbTcpSocketConnection. Outgoing messages are sent by us,
+/// incoming ones are produced by the deserializer from what YourBourse sends
 pub enum FixMessage {
+    Outgoing(YbOutgoingMessage),
+    Incoming(YbIncomingMessage),
+}
+
+impl std::fmt::Display for FixMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Outgoing(message) => write!(f, "{}", message),
+            Self::Incoming(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<YbOutgoingMessage> for FixMessage {
+    fn from(src: YbOutgoingMessage) -> Self {
+        Self::Outgoing(src)
+    }
+}
+
+impl From<YbIncomingMessage> for FixMessage {
+    fn from(src: YbIncomingMessage) -> Self {
+        Self::Incoming(src)
+    }
+}
+
+impl TcpContract for FixMessage {
+    fn is_pong(&self) -> bool {
+        matches!(self, Self::Incoming(YbIncomingMessage::Pong))
+    }
+}
+
+pub enum YbOutgoingMessage {
+    Logon,
+    InstrumentSubscribe(InstrumentSubscribeYbTcpContract),
+    //Sent with MDReqID(262) of the subscription to cancel
+    InstrumentUnsubscribe(InstrumentSubscribeYbTcpContract),
+    PlaceOrder(PlaceOrderYbTcpContract),
+    OrderMassStatusRequest(OrderMassStatusRequestYbTcpContract),
+    Ping,
+}
+
+impl std::fmt::Display for YbOutgoingMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Logon => write!(f, "Logon"),
+            Self::InstrumentSubscribe(contract) => write!(f, "InstrumentSubscribe: {:?}", contract),
+            Self::InstrumentUnsubscribe(contract) => {
+                write!(f, "InstrumentUnsubscribe: {:?}", contract)
+            }
+            Self::PlaceOrder(contract) => write!(f, "PlaceOrder: {:?}", contract),
+            Self::OrderMassStatusRequest(contract) => {
+                write!(f, "OrderMassStatusRequest: {:?}", contract)
+            }
+            Self::Ping => write!(f, "Ping"),
+        }
+    }
+}
+
+pub enum YbIncomingMessage {
     Logon,
     Reject,
     Logout,
-    InstrumentSubscribe(String),
     MarketData(YbMarketData),
     MarketDataReject(String),
     ExecutionReport(ExecutionReportModel),
-    PlaceOrder(PlaceOrderYbTcpContract),
     Others(String),
+    //Heartbeat(0)
     Pong,
-    Ping,
 }
 
-impl FixMessage {
-    pub fn from_slice(src: &[u8]) -> Self {
+impl YbIncomingMessage {
+    pub fn from_slice_with_printer(src: &[u8], fix_printer: &FixMessagePrinter) -> Self {
         let fix_message_reader = FixMessageReader::from_bytes(src);
 
-        if std::env::var("DEBUG_FIX").is_ok() {
-            println!("In  Fix Message: {:?}", fix_message_reader.to_string());
-        }
+        let message_type = match fix_message_reader.get_message_type() {
+            Ok(message_type) => message_type,
+            Err(err) => {
+                return Self::Others(format!(
+                    "Error reading fix message type: {}, Err: {:?}",
+                    fix_printer.print(src),
+                    err
+                ))
+            }
+        };
 
-        match fix_message_reader.get_message_type().unwrap() {
+        match message_type {
             "A" => Self::Logon,
+            "0" => Self::Pong,
             "W" => {
-                let model = crate::deserialize::deserialize_market_data(&fix_message_reader);
+                let model =
+                    crate::deserialize::deserialize_market_data(&fix_message_reader, fix_printer);
                 match model {
                     Ok(model) => Self::MarketData(model),
                     Err(err) => Self::Others(format!(
                         "Error reading fix message: {}, Err: {}",
-                        fix_message_reader.to_string(),
+                        fix_printer.print(src),
                         err
                     )),
                 }
             }
 
-            "Y" => Self::MarketDataReject(fix_message_reader.to_string()),
+            "Y" => Self::MarketDataReject(fix_printer.print(src)),
             "3" => Self::Reject,
             "5" => Self::Logout,
-            "8" => Self::ExecutionReport(ExecutionReportModel::new(&fix_message_reader).unwrap()),
-            _ => Self::Others(fix_message_reader.to_string()),
+            "8" => match ExecutionReportModel::new(&fix_message_reader) {
+                Ok(model) => Self::ExecutionReport(model),
+                Err(err) => Self::Others(format!(
+                    "Error reading execution report: {}, Err: {:?}",
+                    fix_printer.print(src),
+                    err
+                )),
+            },
+            _ => Self::Others(fix_printer.print(src)),
         }
     }
+}
 
-    pub fn to_string(&self) -> String {
+impl std::fmt::Display for YbIncomingMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Logon => "Logon".to_string(),
-            Self::Reject => "Reject".to_string(),
-            Self::Logout => "Logout".to_string(),
-            Self::InstrumentSubscribe(src) => format!("InstrumentSubscribe: {}", src),
-            Self::MarketData(model) => format!("MarketData: {:?}", model),
-            Self::MarketDataReject(src) => format!("MarketDataReject: {}", src),
-            Self::ExecutionReport(model) => format!("ExecutionReport: {:?}", model),
-            Self::PlaceOrder(contract) => format!("PlaceOrder: {:?}", contract),
-            Self::Others(src) => format!("Others: {}", src),
-            Self::Pong => "Pong".to_string(),
-            Self::Ping => "Ping".to_string(),
+            Self::Logon => write!(f, "Logon"),
+            Self::Reject => write!(f, "Reject"),
+            Self::Logout => write!(f, "Logout"),
+            Self::MarketData(model) => write!(f, "MarketData: {:?}", model),
+            Self::MarketDataReject(src) => write!(f, "MarketDataReject: {}", src),
+            Self::ExecutionReport(model) => write!(f, "ExecutionReport: {:?}", model),
+            Self::Others(src) => write!(f, "Others: {}", src),
+            Self::Pong => write!(f, "Pong"),
         }
     }
 }
 
-impl TcpContract for FixMessage {
-    fn is_pong(&self) -> bool {
-        match self {
-            Self::Pong => true,
-            _ => false,
+#[derive(Debug, Clone)]
+pub struct InstrumentSubscribeYbTcpContract {
+    pub md_req_id: String,
+    pub symbol: String,
+}
+
+impl InstrumentSubscribeYbTcpContract {
+    pub fn new(symbol: impl Into<String>, md_req_id: impl Into<String>) -> Self {
+        Self {
+            md_req_id: md_req_id.into(),
+            symbol: symbol.into(),
+        }
+    }
+
+    /// MDReqID is taken from the generator.
+    /// It can be read back from `md_req_id` to track the subscription
+    pub fn generate(symbol: impl Into<String>, generator: &MdReqIdGenerator) -> Self {
+        Self::new(symbol, generator.next())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OrderMassStatusRequestYbTcpContract {
+    pub mass_status_req_id: String,
+}
+
+impl OrderMassStatusRequestYbTcpContract {
+    pub fn new(mass_status_req_id: impl Into<String>) -> Self {
+        Self {
+            mass_status_req_id: mass_status_req_id.into(),
         }
     }
 }
@@ -78,25 +182,32 @@ pub enum PlaceOrderYbTcpContractSide {
     Sell = 2,
 }
 
-impl PlaceOrderYbTcpContractSide {
-    pub fn from_str(src: &str) -> Self {
+impl FromStr for PlaceOrderYbTcpContractSide {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
         match src {
-            "1" => Self::Buy,
-            "2" => Self::Sell,
-            _ => panic!("Unknown side"),
+            "1" => Ok(Self::Buy),
+            "2" => Ok(Self::Sell),
+            _ => Err(format!("Unknown side '{}'", src)),
         }
     }
 }
+
 #[derive(Debug, Clone)]
 pub struct PlaceOrderYbTcpContract {
     pub id: String,
     pub symbol: String,
     pub side: PlaceOrderYbTcpContractSide,
-    pub qty: f64,
+    pub qty: Decimal,
+    //Price the order is expected to be filled at. Not sent, used by pre-trade checks only.
+    //Required if max_price_deviation_percent is set
+    pub expected_price: Option<Decimal>,
 }
 
 #[derive(Debug, Clone)]
 pub enum ExecutionReportModelStatus {
+    New,
     PendingNew,
     PartiallyFilled,
     Filled,
@@ -104,35 +215,45 @@ pub enum ExecutionReportModelStatus {
     Rejected,
 }
 
-impl ExecutionReportModelStatus {
-    pub fn from_str(src: &str) -> Self {
+impl FromStr for ExecutionReportModelStatus {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
         match src {
-            "A" => Self::PendingNew,
-            "1" => Self::PartiallyFilled,
-            "2" => Self::Filled,
-            "4" => Self::Canceled,
-            "8" => Self::Rejected,
-            _ => panic!("Unknown status"),
+            "0" => Ok(Self::New),
+            "A" => Ok(Self::PendingNew),
+            "1" => Ok(Self::PartiallyFilled),
+            "2" => Ok(Self::Filled),
+            "4" => Ok(Self::Canceled),
+            "8" => Ok(Self::Rejected),
+            _ => Err(format!("Unknown status '{}'", src)),
         }
     }
 }
 
 #[derive(Debug, Clone)]
 pub enum ExecutionReportModelExecutionType {
+    New,
     PendingNew,
     Canceled,
     Rejected,
     Trade,
+    //Reply to OrderMassStatusRequest
+    OrderStatus,
 }
 
-impl ExecutionReportModelExecutionType {
-    pub fn from_str(src: &str) -> Self {
+impl FromStr for ExecutionReportModelExecutionType {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
         match src {
-            "A" => Self::PendingNew,
-            "F" => Self::Trade,
-            "4" => Self::Canceled,
-            "8" => Self::Rejected,
-            _ => panic!("Unknown execution type"),
+            "0" => Ok(Self::New),
+            "A" => Ok(Self::PendingNew),
+            "F" => Ok(Self::Trade),
+            "4" => Ok(Self::Canceled),
+            "8" => Ok(Self::Rejected),
+            "I" => Ok(Self::OrderStatus),
+            _ => Err(format!("Unknown execution type '{}'", src)),
         }
     }
 }
@@ -143,12 +264,14 @@ pub enum OrderType {
     Limit,
 }
 
-impl OrderType {
-    pub fn from_str(src: &str) -> Self {
+impl FromStr for OrderType {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
         match src {
-            "1" => Self::Market,
-            "2" => Self::Limit,
-            _ => panic!("Unknown order type"),
+            "1" => Ok(Self::Market),
+            "2" => Ok(Self::Limit),
+            _ => Err(format!("Unknown order type '{}'", src)),
         }
     }
 }
@@ -162,28 +285,35 @@ pub struct ExecutionReportModel {
     pub execution_type: ExecutionReportModelExecutionType,
     pub symbol: String,
     pub side: PlaceOrderYbTcpContractSide,
-    pub qty: f64,
+    pub qty: Decimal,
     pub order_type: OrderType,
-    pub price: Option<f64>,
+    pub price: Option<Decimal>,
     pub time_in_force: Option<String>,
-    pub last_price: Option<f64>,
-    pub avg_price: f64,
+    pub last_price: Option<Decimal>,
+    pub avg_price: Decimal,
     pub trade_date: Option<String>,
     pub reject_reason: Option<String>,
     pub reject_text: Option<String>,
 }
 
-impl ExecutionReportModel {
-    pub fn new(src: &FixMessageReader) -> Result<Self, FixSerializeError> {
-        let price = match src.get_value("44")? {
-            Some(src) => Some(src.parse::<f64>().unwrap()),
-            None => None,
-        };
+#[derive(Debug)]
+pub enum ExecutionReportParseError {
+    Fix(FixSerializeError),
+    MissingTag(&'static str),
+    InvalidDecimal { tag: &'static str, value: String },
+    UnknownValue { tag: &'static str, value: String },
+}
 
-        let last_price = match src.get_value("31")? {
-            Some(src) => Some(src.parse::<f64>().unwrap()),
-            None => None,
-        };
+impl From<FixSerializeError> for ExecutionReportParseError {
+    fn from(src: FixSerializeError) -> Self {
+        Self::Fix(src)
+    }
+}
+
+impl ExecutionReportModel {
+    pub fn new(src: &FixMessageReader) -> Result<Self, ExecutionReportParseError> {
+        let price = read_optional_decimal(src, "44")?;
+        let last_price = read_optional_decimal(src, "31")?;
 
         let trade_date = match src.get_value("75")? {
             Some(src) => Some(src.to_string()),
@@ -201,21 +331,19 @@ impl ExecutionReportModel {
         };
 
         let result = ExecutionReportModel {
-            internal_order_id: src.get_value("11")?.unwrap().to_string(),
-            external_order_id: src.get_value("37")?.unwrap().to_string(),
-            execute_id: src.get_value("17")?.unwrap().to_string(),
-            ord_status: ExecutionReportModelStatus::from_str(src.get_value("39")?.unwrap()),
-            execution_type: ExecutionReportModelExecutionType::from_str(
-                src.get_value("150")?.unwrap(),
-            ),
-            symbol: src.get_value("55")?.unwrap().to_string(),
-            side: PlaceOrderYbTcpContractSide::from_str(src.get_value("54")?.unwrap()),
-            qty: src.get_value("38")?.unwrap().parse().unwrap(),
-            order_type: OrderType::from_str(src.get_value("40")?.unwrap()),
+            internal_order_id: read_required(src, "11")?.to_string(),
+            external_order_id: read_required(src, "37")?.to_string(),
+            execute_id: read_required(src, "17")?.to_string(),
+            ord_status: read_enum(src, "39")?,
+            execution_type: read_enum(src, "150")?,
+            symbol: read_required(src, "55")?.to_string(),
+            side: read_enum(src, "54")?,
+            qty: read_decimal(src, "38")?,
+            order_type: read_enum(src, "40")?,
             price,
             time_in_force: src.get_value("59")?.map(|src| src.to_string()),
             last_price,
-            avg_price: src.get_value("6")?.unwrap().parse().unwrap(),
+            avg_price: read_decimal(src, "6")?,
             trade_date,
             reject_reason,
             reject_text,
@@ -225,6 +353,48 @@ impl ExecutionReportModel {
     }
 }
 
+fn read_required<'s>(
+    src: &'s FixMessageReader,
+    tag: &'static str,
+) -> Result<&'s str, ExecutionReportParseError> {
+    src.get_value(tag)?
+        .ok_or(ExecutionReportParseError::MissingTag(tag))
+}
+
+fn read_enum<T: FromStr>(
+    src: &FixMessageReader,
+    tag: &'static str,
+) -> Result<T, ExecutionReportParseError> {
+    let value = read_required(src, tag)?;
+    value
+        .parse()
+        .map_err(|_| ExecutionReportParseError::UnknownValue {
+            tag,
+            value: value.to_string(),
+        })
+}
+
+fn read_decimal(
+    src: &FixMessageReader,
+    tag: &'static str,
+) -> Result<Decimal, ExecutionReportParseError> {
+    let value = read_required(src, tag)?;
+    parse_fix_decimal(value).map_err(|_| ExecutionReportParseError::InvalidDecimal {
+        tag,
+        value: value.to_string(),
+    })
+}
+
+fn read_optional_decimal(
+    src: &FixMessageReader,
+    tag: &'static str,
+) -> Result<Option<Decimal>, ExecutionReportParseError> {
+    match src.get_value(tag)? {
+        Some(_) => read_decimal(src, tag).map(Some),
+        None => Ok(None),
+    }
+}
+
 /*
 impl Into<ExecutionReportModel> for FixMessageBuilder {
     fn into(self) -> ExecutionReportModel {
@@ -284,6 +454,79 @@ impl Into<ExecutionReportModel> for FixMessageBuilder {
 pub struct YbMarketData {
     pub instrument_id: String,
     pub date: DateTimeAsMicroseconds,
-    pub bid: f64,
-    pub ask: f64,
+    pub bid: Decimal,
+    pub ask: Decimal,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn execution_report(fields: &str) -> Vec<u8> {
+        crate::tests_support::frame(&format!(
+            "35=8\x0149=TARGET\x0156=SENDER\x0134=2\x0152=20240425-17:28:02.629\x01{}",
+            fields
+        ))
+    }
+
+    #[test]
+    fn test_reads_execution_report() {
+        let src = execution_report(
+            "11=1\x0137=E1\x0117=X1\x0139=2\x01150=F\x0155=EURUSD\x0154=1\x0138=1000\x01\
+            40=1\x0131=1.07015\x016=1.07015\x01",
+        );
+
+        let model = ExecutionReportModel::new(&FixMessageReader::from_bytes(&src)).unwrap();
+
+        assert_eq!(model.qty, Decimal::new(1000, 0));
+        assert_eq!(model.last_price, Some(Decimal::new(107015, 5)));
+        assert_eq!(model.price, None);
+    }
+
+    #[test]
+    fn test_reads_mass_status_reply() {
+        let src = execution_report(
+            "11=1\x0137=E1\x0117=X1\x0139=0\x01150=I\x0155=EURUSD\x0154=2\x0138=1000\x01\
+            40=2\x0144=1.07015\x016=0\x01584=MS-1\x01",
+        );
+
+        let model = ExecutionReportModel::new(&FixMessageReader::from_bytes(&src)).unwrap();
+
+        assert!(matches!(model.ord_status, ExecutionReportModelStatus::New));
+        assert!(matches!(
+            model.execution_type,
+            ExecutionReportModelExecutionType::OrderStatus
+        ));
+        assert_eq!(model.price, Some(Decimal::new(107015, 5)));
+    }
+
+    #[test]
+    fn test_unknown_value_is_an_error() {
+        let src = execution_report(
+            "11=1\x0137=E1\x0117=X1\x0139=2\x01150=Z\x0155=EURUSD\x0154=1\x0138=1000\x01\
+            40=1\x016=1.07015\x01",
+        );
+
+        let result = ExecutionReportModel::new(&FixMessageReader::from_bytes(&src));
+
+        assert!(matches!(
+            result,
+            Err(ExecutionReportParseError::UnknownValue { tag: "150", .. })
+        ));
+    }
+
+    #[test]
+    fn test_invalid_decimal_is_an_error() {
+        let src = execution_report(
+            "11=1\x0137=E1\x0117=X1\x0139=2\x01150=F\x0155=EURUSD\x0154=1\x0138=1000\x01\
+            40=1\x0131=1.07O15\x016=1.07015\x01",
+        );
+
+        let result = ExecutionReportModel::new(&FixMessageReader::from_bytes(&src));
+
+        assert!(matches!(
+            result,
+            Err(ExecutionReportParseError::InvalidDecimal { tag: "31", .. })
+        ));
+    }
 }
@@ -1,106 +1,401 @@
-use std::sync::atomic::AtomicU64;
-
 use my_tcp_sockets::{
     socket_reader::{ReadBuffer, ReadingTcpContractFail, SocketReader},
     TcpSocketSerializer, TcpWriteBuffer,
 };
-use rust_fix::FixMessageItem;
 
-use crate::{tcp_messages::*, YbTcpSate, FIX_DELIMITER_AS_ARR};
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+use rust_fix::FixMessageReader;
+
+use crate::{
+    framing::{FixFramingError, CHECKSUM_TAG_MARKER},
+    journal::FixJournalDirection,
+    logging::YbFixLogContext,
+    serialize::YbSerializeError,
+    tcp_messages::*,
+    YbFixSettings, YbSendError, YbTcpSate, DEFAULT_MAX_MESSAGE_SIZE, DEFAULT_READ_BUFFER_SIZE,
+    FIX_DELIMITER_AS_ARR,
+};
 
 pub struct YourBourseFixTcpSerializer {
-    message_counter: AtomicU64,
     buffer: ReadBuffer,
+    buffer_size: usize,
+    max_message_size: usize,
+    //Reused between messages so a frame is assembled without per message allocations
+    payload: Vec<u8>,
 }
 
 impl YourBourseFixTcpSerializer {
     pub fn new() -> Self {
+        Self::with_buffer_size(DEFAULT_READ_BUFFER_SIZE, DEFAULT_MAX_MESSAGE_SIZE)
+    }
+
+    pub fn from_settings(settings: &YbFixSettings) -> Self {
+        Self::with_buffer_size(settings.read_buffer_size, settings.max_message_size)
+    }
+
+    pub fn with_buffer_size(buffer_size: usize, max_message_size: usize) -> Self {
         Self {
-            message_counter: AtomicU64::new(1),
-            buffer: ReadBuffer::new(2048 * 24),
+            buffer: ReadBuffer::new(buffer_size),
+            buffer_size,
+            max_message_size,
+            payload: Vec::with_capacity(2048),
         }
     }
 
-    fn get_next_message_id(&self) -> u64 {
-        self.message_counter
-            .fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+    async fn read_chunk(
+        &mut self,
+        socket_reader: &mut impl SocketReader,
+        end_marker: &[u8],
+    ) -> Result<(), ReadingTcpContractFail> {
+        let chunk = socket_reader
+            .read_until_end_marker(&mut self.buffer, end_marker)
+            .await?;
+        self.payload.extend_from_slice(chunk);
+        Ok(())
     }
 
     async fn receive_fix_payload(
         &mut self,
         socket_reader: &mut impl SocketReader,
-    ) -> Result<Vec<u8>, ReadingTcpContractFail> {
-        let mut result = vec![];
-        loop {
-            let chunk = socket_reader
-                .read_until_end_marker(&mut self.buffer, &FIX_DELIMITER_AS_ARR.as_slice())
-                .await;
-            match chunk {
-                Ok(res) => {
-                    result.extend_from_slice(res);
-                    let item = FixMessageItem::from_slice(res);
-                    if item.key == "10".to_string() {
-                        break;
-                    }
-                }
-                Err(err) => {
-                    println!("Err: {:?}", err);
-                    break;
+        state: &YbTcpSate,
+    ) -> Result<&[u8], ReadingTcpContractFail> {
+        self.payload.clear();
+
+        // BeginString(8) and BodyLength(9)
+        for _ in 0..2 {
+            if let Err(err) = self
+                .read_chunk(socket_reader, FIX_DELIMITER_AS_ARR.as_slice())
+                .await
+            {
+                return Err(report_read_error(err, &self.payload, state));
+            }
+        }
+
+        let header = match crate::framing::parse_header(self.payload.as_slice()) {
+            Ok(Some(header)) => Ok(header),
+            Ok(None) => Err(FixFramingError::InvalidBodyLength(
+                String::from_utf8_lossy(self.payload.as_slice()).to_string(),
+            )),
+            Err(err) => Err(err),
+        };
+
+        let header = match header {
+            Ok(header) if header.get_frame_len() > self.max_message_size => {
+                Err(FixFramingError::MessageTooLarge {
+                    size: header.get_frame_len(),
+                    max_size: self.max_message_size,
+                })
+            }
+            result => result,
+        };
+
+        let header = match header {
+            Ok(header) => header,
+            Err(err) => return Err(report_framing_error(err, &self.payload, state)),
+        };
+
+        let body_end = header.header_len + header.body_length;
+
+        // Usually the body is read at once: everything up to <SOH>10=
+        // and then the CheckSum(10) value.
+        // If <SOH>10= is found inside of the body, BodyLength(9) tells us to keep reading.
+        // Messages which do not fit into the read buffer are assembled field by field
+        let body_end_marker: &[u8] = if header.get_frame_len() <= self.buffer_size {
+            &CHECKSUM_TAG_MARKER
+        } else {
+            &FIX_DELIMITER_AS_ARR
+        };
+
+        while self.payload.len() < body_end {
+            if let Err(err) = self.read_chunk(socket_reader, body_end_marker).await {
+                if body_end_marker == FIX_DELIMITER_AS_ARR.as_slice() {
+                    state.logger.write_warning(
+                        "ReceiveFixPayload",
+                        format!(
+                            "Message of {} bytes is read field by field. \
+                            A field longer than read_buffer_size of {} bytes can not be read",
+                            header.get_frame_len(),
+                            self.buffer_size
+                        ),
+                        YbFixLogContext::from_settings(&state.settings),
+                    );
                 }
-            };
+
+                return Err(report_read_error(err, &self.payload, state));
+            }
+        }
+
+        if let Err(err) = self
+            .read_chunk(socket_reader, FIX_DELIMITER_AS_ARR.as_slice())
+            .await
+        {
+            return Err(report_read_error(err, &self.payload, state));
         }
 
-        if result.len() == 0 {
-            return Err(ReadingTcpContractFail::ErrorReadingSize);
+        let framing_result = match crate::framing::locate_frame(self.payload.as_slice()) {
+            Ok(Some(frame_len)) if frame_len == self.payload.len() => Ok(()),
+            Ok(_) => Err(FixFramingError::BodyLengthMismatch),
+            Err(err) => Err(err),
+        };
+
+        if let Err(err) = framing_result {
+            return Err(report_framing_error(err, &self.payload, state));
         }
 
-        Ok(result)
+        Ok(self.payload.as_slice())
+    }
+}
+
+// Logout at the end of the session is expected, any other one is worth a warning.
+// YourBourse may log out a heartbeat interval before the scheduled end
+fn report_logout(state: &YbTcpSate, received_at: DateTimeAsMicroseconds) {
+    let log_ctx = YbFixLogContext::from_settings(&state.settings).with_msg_type("5");
+
+    let grace = state.settings.heartbeat_interval_sec as i64 * 1_000_000;
+
+    let expected = match state.session.get_session_end() {
+        Some(session_end) => received_at.unix_microseconds >= session_end.unix_microseconds - grace,
+        None => false,
+    };
+
+    if !expected {
+        state.logger.write_warning(
+            "Logout",
+            "Logout is received while the session is open".to_string(),
+            log_ctx,
+        );
+    } else {
+        state.logger.write_info(
+            "Logout",
+            "Logout is received at the end of the session".to_string(),
+            log_ctx,
+        );
+    }
+}
+
+fn report_framing_error(
+    err: FixFramingError,
+    received: &[u8],
+    state: &YbTcpSate,
+) -> ReadingTcpContractFail {
+    state.logger.write_error(
+        "ReceiveFixPayload",
+        format!(
+            "Invalid fix frame: {}. Received: {}",
+            err,
+            state.logger.get_fix_printer().print(received)
+        ),
+        YbFixLogContext::from_settings(&state.settings),
+    );
+
+    journal_rejected(received, state);
+
+    ReadingTcpContractFail::ErrorReadingSize
+}
+
+// The partially received frame is dropped and the error is returned as is,
+// so the connection is closed instead of parsing a truncated message
+fn report_read_error(
+    err: ReadingTcpContractFail,
+    received: &[u8],
+    state: &YbTcpSate,
+) -> ReadingTcpContractFail {
+    state.logger.write_warning(
+        "ReceiveFixPayload",
+        format!(
+            "Error reading fix payload: {:?}. Dropped {} bytes of incomplete message",
+            err,
+            received.len()
+        ),
+        YbFixLogContext::from_settings(&state.settings),
+    );
+
+    journal_rejected(received, state);
+
+    err
+}
+
+// Bytes which never became a message are journaled as received, for the audit
+fn journal_rejected(received: &[u8], state: &YbTcpSate) {
+    if received.is_empty() {
+        return;
+    }
+
+    if let Some(journal) = state.journal.as_ref() {
+        journal.write(FixJournalDirection::Inbound, state.clock.now(), received);
     }
 }
 
 #[async_trait::async_trait]
 impl TcpSocketSerializer<FixMessage, YbTcpSate> for YourBourseFixTcpSerializer {
     fn serialize(&self, out: &mut impl TcpWriteBuffer, contract: &FixMessage, state: &YbTcpSate) {
-        let fix_message_writer = match contract {
-            FixMessage::InstrumentSubscribe(instrument) => crate::serialize::instrument_subscribe(
-                &state.settings,
-                instrument,
-                self.get_next_message_id(),
-            ),
-            FixMessage::Logon => {
-                crate::serialize::logon(&state.settings, self.get_next_message_id())
+        // Checked before the sequence number is taken, so a rejected contract does not leave a gap
+        let outgoing = match contract {
+            FixMessage::Outgoing(outgoing) => outgoing,
+            FixMessage::Incoming(_) => {
+                state.logger.write_error(
+                    "Serialize",
+                    YbSerializeError::NotOutgoingMessage(contract.to_string()).to_string(),
+                    YbFixLogContext::from_settings(&state.settings),
+                );
+                return;
+            }
+        };
+
+        let now = state.clock.now();
+
+        if let Err(err) = crate::serialize::validate_contract(&state.settings, outgoing) {
+            state.logger.write_error(
+                "Serialize",
+                err.to_string(),
+                YbFixLogContext::from_settings(&state.settings),
+            );
+
+            if let YbOutgoingMessage::PlaceOrder(order) = outgoing {
+                state.release_order(order);
+            }
+
+            return;
+        }
+
+        // Orders written with connection.send are checked here only. Checking again an order
+        // accepted by YbTcpSate::send keeps its reservation
+        if let YbOutgoingMessage::PlaceOrder(order) = outgoing {
+            if let Err(reason) = state.check_order(order) {
+                state.release_order(order);
+                state.logger.write_error(
+                    "Serialize",
+                    YbSendError::RiskRejected {
+                        id: order.id.clone(),
+                        reason,
+                    }
+                    .to_string(),
+                    YbFixLogContext::from_settings(&state.settings),
+                );
+                return;
             }
-            FixMessage::Ping => crate::serialize::ping(&state.settings, self.get_next_message_id()),
-            FixMessage::PlaceOrder(contract) => crate::serialize::place_order_contract(
-                &state.settings,
-                contract,
-                self.get_next_message_id(),
-            ),
-            _ => {
-                panic!(
-                    "Contract {} can not be used as outgoing fix message",
-                    contract.to_string()
+        }
+
+        // YourBourse would reject Logon outside of the session
+        if let YbOutgoingMessage::Logon = outgoing {
+            if !state.settings.is_session_open(now) {
+                state.logger.write_warning(
+                    "Logon",
+                    "Logon is not sent: the session is closed".to_string(),
+                    YbFixLogContext::from_settings(&state.settings),
                 );
+                return;
             }
+        }
+
+        let reset_seq_num = match outgoing {
+            YbOutgoingMessage::Logon => state.session.should_reset_seq_num(&state.settings, now),
+            _ => false,
         };
 
-        out.write_slice(fix_message_writer.compile_message().as_slice());
+        if reset_seq_num {
+            state.session.reset_seq_num();
+        }
+
+        let seq_num = state.session.get_next_seq_num();
+        let fix_message_writer = crate::serialize::serialize_contract(
+            &state.settings,
+            outgoing,
+            seq_num,
+            reset_seq_num,
+            now,
+        );
+
+        let payload = fix_message_writer.compile_message();
+
+        if let YbOutgoingMessage::Logon = outgoing {
+            state.logger.write_info(
+                "Logon",
+                format!(
+                    "Logon message: {}",
+                    state.logger.get_fix_printer().print(payload.as_slice())
+                ),
+                YbFixLogContext::from_settings(&state.settings)
+                    .with_seq_num(seq_num)
+                    .with_msg_type("A"),
+            );
+        }
+
+        match outgoing {
+            YbOutgoingMessage::InstrumentSubscribe(contract) => state.subscriptions.add(contract),
+            YbOutgoingMessage::InstrumentUnsubscribe(contract) => {
+                state.subscriptions.remove(&contract.md_req_id);
+            }
+            YbOutgoingMessage::PlaceOrder(order) => {
+                for check in state.pre_trade_checks.iter() {
+                    check.on_order_sent(order);
+                }
+            }
+            _ => {}
+        }
+
+        state.logger.trace_raw("Out", payload.as_slice());
+
+        if let Some(journal) = state.journal.as_ref() {
+            journal.write(FixJournalDirection::Outbound, now, payload.as_slice());
+        }
+
+        out.write_slice(payload.as_slice());
     }
 
     fn get_ping(&self) -> FixMessage {
-        return FixMessage::Ping;
+        return YbOutgoingMessage::Ping.into();
     }
 
     async fn deserialize<TSocketReader: Send + Sync + 'static + SocketReader>(
         &mut self,
         socket_reader: &mut TSocketReader,
-        _state: &YbTcpSate,
+        state: &YbTcpSate,
     ) -> Result<FixMessage, ReadingTcpContractFail> {
-        let fix_payload = self.receive_fix_payload(socket_reader).await?;
+        let fix_payload = self.receive_fix_payload(socket_reader, state).await?;
+
+        let received_at = state.clock.now();
+
+        if let Some(journal) = state.journal.as_ref() {
+            journal.write(FixJournalDirection::Inbound, received_at, fix_payload);
+        }
+
+        state.logger.trace_raw("In", fix_payload);
+
+        let fix_message =
+            YbIncomingMessage::from_slice_with_printer(fix_payload, state.logger.get_fix_printer());
+
+        match &fix_message {
+            YbIncomingMessage::Logon => {
+                state.session.report_logon(&state.settings, received_at);
+
+                if let Some(endpoint_selector) = state.endpoint_selector.as_ref() {
+                    endpoint_selector.report_connected();
+                }
+            }
+            YbIncomingMessage::Logout => report_logout(state, received_at),
+            // Subscription is remembered when it's sent, so it's not restored once rejected
+            YbIncomingMessage::MarketDataReject(_) => {
+                let fix_message_reader = FixMessageReader::from_bytes(fix_payload);
 
-        let fix_message = FixMessage::from_slice(fix_payload.as_slice());
+                if let Ok(Some(md_req_id)) = fix_message_reader.get_value("262") {
+                    state.subscriptions.remove(md_req_id);
+                }
+            }
+            YbIncomingMessage::MarketData(market_data) => {
+                for check in state.pre_trade_checks.iter() {
+                    check.on_market_data(market_data);
+                }
+            }
+            YbIncomingMessage::ExecutionReport(execution_report) => {
+                for check in state.pre_trade_checks.iter() {
+                    check.on_execution_report(execution_report);
+                }
+            }
+            _ => {}
+        }
 
-        return Ok(fix_message);
+        return Ok(fix_message.into());
 
         /*
         match FixMessageBuilder::from_bytes(&result, false) {
@@ -125,3 +420,172 @@ impl TcpSocketSerializer<FixMessage, YbTcpSate> for YourBourseFixTcpSerializer {
          */
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use my_tcp_sockets::socket_reader::{ReadingTcpContractFail, SocketReaderInMem};
+    use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+    use super::*;
+    use crate::journal::FixJournal;
+    use crate::tests_support::frame;
+
+    const HEARTBEAT: &[u8] = b"8=FIX.4.4\x019=5\x0135=0\x0110=163\x01";
+
+    #[derive(Default)]
+    struct TestJournal {
+        records: Mutex<Vec<(FixJournalDirection, Vec<u8>)>>,
+    }
+
+    impl FixJournal for TestJournal {
+        fn write(
+            &self,
+            direction: FixJournalDirection,
+            _timestamp: DateTimeAsMicroseconds,
+            payload: &[u8],
+        ) {
+            self.records
+                .lock()
+                .unwrap()
+                .push((direction, payload.to_vec()));
+        }
+    }
+
+    fn state() -> (YbTcpSate, Arc<TestJournal>) {
+        let settings = crate::tests_support::settings();
+
+        let journal = Arc::new(TestJournal::default());
+        let state = YbTcpSate::new(settings).with_journal(journal.clone());
+        (state, journal)
+    }
+
+    async fn receive(
+        serializer: &mut YourBourseFixTcpSerializer,
+        socket_reader: &mut SocketReaderInMem,
+        state: &YbTcpSate,
+    ) -> Result<Vec<u8>, ReadingTcpContractFail> {
+        serializer
+            .receive_fix_payload(socket_reader, state)
+            .await
+            .map(|payload| payload.to_vec())
+    }
+
+    #[tokio::test]
+    async fn test_receives_consecutive_frames() {
+        let (state, journal) = state();
+        let mut serializer = YourBourseFixTcpSerializer::new();
+
+        let mut src = HEARTBEAT.to_vec();
+        src.extend_from_slice(HEARTBEAT);
+        let mut socket_reader = SocketReaderInMem::new(src);
+
+        for _ in 0..2 {
+            let payload = receive(&mut serializer, &mut socket_reader, &state).await;
+            assert_eq!(payload.unwrap().as_slice(), HEARTBEAT);
+        }
+
+        assert!(journal.records.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_corrupt_frames_and_journals_them() {
+        for src in [
+            // CheckSum(10) mismatch
+            &b"8=FIX.4.4\x019=5\x0135=1\x0110=163\x01"[..],
+            // BodyLength(9) is shorter than the body
+            b"8=FIX.4.4\x019=4\x0135=0\x0110=163\x01",
+            // BodyLength(9) is not a number
+            b"8=FIX.4.4\x019=X\x0135=0\x0110=163\x01",
+            // Does not start with BeginString(8)
+            b"9=5\x018=FIX.4.4\x0135=0\x0110=163\x01",
+        ] {
+            let (state, journal) = state();
+            let mut serializer = YourBourseFixTcpSerializer::new();
+            let mut socket_reader = SocketReaderInMem::new(src.to_vec());
+
+            let result = receive(&mut serializer, &mut socket_reader, &state).await;
+            assert!(
+                matches!(result, Err(ReadingTcpContractFail::ErrorReadingSize)),
+                "{}",
+                String::from_utf8_lossy(src)
+            );
+
+            let records = journal.records.lock().unwrap();
+            assert_eq!(records.len(), 1);
+            assert_eq!(records[0].0, FixJournalDirection::Inbound);
+            assert!(src.starts_with(records[0].1.as_slice()));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_assembles_message_bigger_than_buffer_field_by_field() {
+        let (state, journal) = state();
+        let mut serializer = YourBourseFixTcpSerializer::with_buffer_size(16, 1024);
+
+        // <SOH>10= inside of the text must not end the body
+        let message = frame("35=3\x0158=Text\x0110=0\x0145=1\x0158=Second\x01");
+        let mut src = message.clone();
+        src.extend_from_slice(HEARTBEAT);
+        let mut socket_reader = SocketReaderInMem::new(src);
+
+        let payload = receive(&mut serializer, &mut socket_reader, &state).await;
+        assert_eq!(payload.unwrap(), message);
+
+        let payload = receive(&mut serializer, &mut socket_reader, &state).await;
+        assert_eq!(payload.unwrap().as_slice(), HEARTBEAT);
+
+        assert!(journal.records.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_field_longer_than_buffer() {
+        let (state, journal) = state();
+        let mut serializer = YourBourseFixTcpSerializer::with_buffer_size(16, 1024);
+
+        let message = frame("35=3\x0158=Text which does not fit into the buffer\x01");
+        let mut socket_reader = SocketReaderInMem::new(message.clone());
+
+        let result = receive(&mut serializer, &mut socket_reader, &state).await;
+        assert!(result.is_err());
+
+        let records = journal.records.lock().unwrap();
+        assert_eq!(records.len(), 1);
+        assert!(message.starts_with(records[0].1.as_slice()));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_message_bigger_than_max_message_size() {
+        let (state, journal) = state();
+        let mut serializer = YourBourseFixTcpSerializer::with_buffer_size(16, HEARTBEAT.len() - 1);
+        let mut socket_reader = SocketReaderInMem::new(HEARTBEAT.to_vec());
+
+        let result = receive(&mut serializer, &mut socket_reader, &state).await;
+        assert!(matches!(
+            result,
+            Err(ReadingTcpContractFail::ErrorReadingSize)
+        ));
+
+        // Only BeginString(8) and BodyLength(9) are read before the message is rejected
+        let records = journal.records.lock().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].1.as_slice(), b"8=FIX.4.4\x019=5\x01");
+    }
+
+    #[tokio::test]
+    async fn test_rejects_truncated_frame() {
+        for len in [HEARTBEAT.len() - 1, 20, 12] {
+            let (state, journal) = state();
+            let mut serializer = YourBourseFixTcpSerializer::new();
+            let mut socket_reader = SocketReaderInMem::new(HEARTBEAT[..len].to_vec());
+
+            let result = receive(&mut serializer, &mut socket_reader, &state).await;
+            assert!(result.is_err(), "{}", len);
+
+            let records = journal.records.lock().unwrap();
+            assert_eq!(records.len(), 1);
+            assert!(HEARTBEAT.starts_with(records[0].1.as_slice()));
+        }
+    }
+}
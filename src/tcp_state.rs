@@ -1,19 +1,190 @@
 use my_tcp_sockets::TcpSerializerState;
 
-use crate::{tcp_messages::FixMessage, YbFixSettings};
+use std::sync::Arc;
 
+use crate::{
+    clock::{FixClock, SystemFixClock},
+    failover::YbEndpointSelector,
+    journal::{FixJournal, RollingFileFixJournal},
+    logging::YbFixLogger,
+    risk::{PreTradeCheck, YbPreTradeRiskChecker, YbRiskRejectReason},
+    serialize::{validate_contract, YbSerializeError},
+    session::YbFixSession,
+    subscriptions::YbSubscriptionRegistry,
+    tcp_messages::{FixMessage, PlaceOrderYbTcpContract, YbOutgoingMessage},
+    throttle::YbOutboundThrottle,
+    YbFixSettings,
+};
+
+#[derive(Debug)]
+pub enum YbSendError {
+    NotConnected(String),
+    //YourBourse would reject the message, so it's not sent
+    Invalid(YbSerializeError),
+    RiskRejected {
+        id: String,
+        reason: YbRiskRejectReason,
+    },
+}
+
+impl std::fmt::Display for YbSendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotConnected(message) => write!(f, "{} is not sent: not connected", message),
+            Self::Invalid(err) => write!(f, "{}", err),
+            Self::RiskRejected { id, reason } => write!(
+                f,
+                "Order '{}' is rejected by pre-trade checks: {}",
+                id, reason
+            ),
+        }
+    }
+}
+
+impl std::error::Error for YbSendError {}
+
+/// Create it once and give every connection a clone. Clones share the FIX session,
+/// subscriptions, throttle and pre-trade checks, so they outlive a single connection
+#[derive(Clone)]
 pub struct YbTcpSate {
     pub settings: YbFixSettings,
+    pub session: Arc<YbFixSession>,
+    pub logger: Arc<YbFixLogger>,
+    pub journal: Option<Arc<dyn FixJournal>>,
+    pub clock: Arc<dyn FixClock>,
+    pub subscriptions: Arc<YbSubscriptionRegistry>,
+    pub endpoint_selector: Option<Arc<YbEndpointSelector>>,
+    pub throttle: Arc<YbOutboundThrottle>,
+    pub pre_trade_checks: Vec<Arc<dyn PreTradeCheck>>,
 }
 
 impl YbTcpSate {
     pub fn new(settings: YbFixSettings) -> Self {
-        Self { settings }
+        let logger = Arc::new(YbFixLogger::from_settings(&settings));
+
+        let journal: Option<Arc<dyn FixJournal>> = match settings.journal_folder.as_ref() {
+            Some(folder) => Some(Arc::new(RollingFileFixJournal::new(
+                folder.as_str(),
+                format!(
+                    "{}-{}",
+                    settings.sender_company_id, settings.target_company_id
+                ),
+            ))),
+            None => None,
+        };
+
+        let throttle = Arc::new(YbOutboundThrottle::from_settings(&settings));
+
+        let mut pre_trade_checks: Vec<Arc<dyn PreTradeCheck>> = vec![];
+
+        if let Some(risk_limits) = settings.risk_limits.as_ref() {
+            pre_trade_checks.push(Arc::new(YbPreTradeRiskChecker::new(risk_limits.clone())));
+        }
+
+        Self {
+            settings,
+            session: Arc::new(YbFixSession::new()),
+            logger,
+            journal,
+            clock: Arc::new(SystemFixClock),
+            subscriptions: Arc::new(YbSubscriptionRegistry::new()),
+            endpoint_selector: None,
+            throttle,
+            pre_trade_checks,
+        }
+    }
+
+    /// Shares the logger between connections so raw tracing can be toggled from the outside
+    pub fn with_logger(mut self, logger: Arc<YbFixLogger>) -> Self {
+        self.logger = logger;
+        self
+    }
+
+    pub fn with_journal(mut self, journal: Arc<dyn FixJournal>) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn FixClock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Same selector has to be given to the TcpClient as its settings,
+    /// so it learns when Logon succeeds on the endpoint it picked
+    pub fn with_endpoint_selector(mut self, endpoint_selector: Arc<YbEndpointSelector>) -> Self {
+        self.endpoint_selector = Some(endpoint_selector);
+        self
+    }
+
+    /// Checks are run in the order they are added
+    pub fn with_pre_trade_check(mut self, check: Arc<dyn PreTradeCheck>) -> Self {
+        self.pre_trade_checks.push(check);
+        self
     }
 
     pub fn get_settings(&self) -> &YbFixSettings {
         &self.settings
     }
+
+    /// Runs every pre-trade check. Accepted order is counted as open by the checks
+    /// until it's reported done by an ExecutionReport or released with `release_order`
+    pub fn check_order(&self, order: &PlaceOrderYbTcpContract) -> Result<(), YbRiskRejectReason> {
+        for (index, check) in self.pre_trade_checks.iter().enumerate() {
+            if let Err(reason) = check.check(order) {
+                for check in self.pre_trade_checks[..index].iter() {
+                    check.on_order_not_sent(order);
+                }
+
+                return Err(reason);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Order accepted by `check_order` is not going to be sent
+    pub fn release_order(&self, order: &PlaceOrderYbTcpContract) {
+        for check in self.pre_trade_checks.iter() {
+            check.on_order_not_sent(order);
+        }
+    }
+
+    pub async fn place_order(&self, order: PlaceOrderYbTcpContract) -> Result<(), YbSendError> {
+        self.send(YbOutgoingMessage::PlaceOrder(order)).await
+    }
+
+    /// Sends through the current connection of the session once the throttle allows it.
+    /// The message is validated and PlaceOrder is checked by the pre-trade checks first.
+    /// Use it for every application message: `connection.send` bypasses the throttle.
+    /// The connection is set by YbSessionCallback
+    pub async fn send(&self, message: YbOutgoingMessage) -> Result<(), YbSendError> {
+        validate_contract(&self.settings, &message).map_err(YbSendError::Invalid)?;
+
+        if let YbOutgoingMessage::PlaceOrder(order) = &message {
+            self.check_order(order)
+                .map_err(|reason| YbSendError::RiskRejected {
+                    id: order.id.clone(),
+                    reason,
+                })?;
+        }
+
+        self.throttle.acquire(&message).await;
+
+        let connection = match self.session.get_connection() {
+            Some(connection) => connection,
+            None => {
+                if let YbOutgoingMessage::PlaceOrder(order) = &message {
+                    self.release_order(order);
+                }
+
+                return Err(YbSendError::NotConnected(message.to_string()));
+            }
+        };
+
+        connection.send(&message.into()).await;
+        Ok(())
+    }
 }
 
 impl TcpSerializerState<FixMessage> for YbTcpSate {
@@ -22,3 +193,80 @@ impl TcpSerializerState<FixMessage> for YbTcpSate {
     }
     fn apply_tcp_contract(&mut self, _: &FixMessage) {}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        risk::YbRiskLimits,
+        tcp_messages::{OrderMassStatusRequestYbTcpContract, PlaceOrderYbTcpContractSide},
+        Decimal,
+    };
+
+    #[tokio::test]
+    async fn test_send_requires_connection() {
+        let state = YbTcpSate::new(crate::tests_support::settings());
+
+        let message = YbOutgoingMessage::OrderMassStatusRequest(
+            OrderMassStatusRequestYbTcpContract::new("MS-1"),
+        );
+
+        assert!(matches!(
+            state.clone().send(message).await,
+            Err(YbSendError::NotConnected(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_place_order_returns_reject_reason() {
+        let mut settings = crate::tests_support::settings();
+        settings.risk_limits = Some(YbRiskLimits {
+            max_order_qty: Some(Decimal::ONE),
+            max_open_orders: Some(1),
+            ..Default::default()
+        });
+
+        let state = YbTcpSate::new(settings);
+        let order = |id: &str, qty: Decimal| PlaceOrderYbTcpContract {
+            id: id.to_string(),
+            symbol: "EURUSD".to_string(),
+            side: PlaceOrderYbTcpContractSide::Buy,
+            qty,
+            expected_price: None,
+        };
+
+        assert!(matches!(
+            state.place_order(order("1", Decimal::TWO)).await,
+            Err(YbSendError::RiskRejected {
+                reason: YbRiskRejectReason::MaxOrderQtyExceeded { .. },
+                ..
+            })
+        ));
+
+        // Slot taken by the check is released when the order is not sent
+        assert!(matches!(
+            state.place_order(order("2", Decimal::ONE)).await,
+            Err(YbSendError::NotConnected(_))
+        ));
+        assert!(state.check_order(&order("3", Decimal::ONE)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_place_order_rejects_invalid_cl_ord_id() {
+        let state = YbTcpSate::new(crate::tests_support::settings());
+        let order = PlaceOrderYbTcpContract {
+            id: "order 1".to_string(),
+            symbol: "EURUSD".to_string(),
+            side: PlaceOrderYbTcpContractSide::Buy,
+            qty: Decimal::ONE,
+            expected_price: None,
+        };
+
+        assert!(matches!(
+            state.place_order(order).await,
+            Err(YbSendError::Invalid(
+                YbSerializeError::InvalidClOrdId { .. }
+            ))
+        ));
+    }
+}
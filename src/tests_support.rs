@@ -0,0 +1,16 @@
+use crate::YbFixSettings;
+
+/// Settings every test starts from
+pub fn settings() -> YbFixSettings {
+    YbFixSettings::new(
+        "fix.yourbourse.com:5001".to_string(),
+        "secret".to_string(),
+        "SENDER".to_string(),
+        "TARGET".to_string(),
+    )
+}
+
+/// Inbound message with the given body fields, e.g. `35=0\x01`
+pub fn frame(body: &str) -> Vec<u8> {
+    crate::framing::build_frame(body.as_bytes())
+}
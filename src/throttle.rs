@@ -0,0 +1,200 @@
+use std::{
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{YbFixSettings, YbOutgoingMessage};
+
+/// Sustained rate and the number of messages which can be sent at once after a pause
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct YbRateLimit {
+    pub messages_per_sec: f64,
+    pub burst: u32,
+}
+
+/// `messages_per_sec,burst`, e.g. `10,20`
+impl FromStr for YbRateLimit {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        let err = || format!("Invalid rate limit '{}'", src);
+
+        let (messages_per_sec, burst) = src.split_once(',').ok_or_else(err)?;
+
+        Ok(Self {
+            messages_per_sec: messages_per_sec.trim().parse().map_err(|_| err())?,
+            burst: burst.trim().parse().map_err(|_| err())?,
+        })
+    }
+}
+
+struct YbTokenBucketState {
+    tokens: f64,
+    updated: Instant,
+}
+
+pub struct YbTokenBucket {
+    limit: YbRateLimit,
+    state: Mutex<YbTokenBucketState>,
+}
+
+impl YbTokenBucket {
+    pub fn new(limit: YbRateLimit) -> Self {
+        Self {
+            limit,
+            state: Mutex::new(YbTokenBucketState {
+                tokens: limit.burst as f64,
+                updated: Instant::now(),
+            }),
+        }
+    }
+
+    /// Takes a token or returns how long to wait until the next one is available
+    pub fn try_acquire_at(&self, now: Instant) -> Result<(), Duration> {
+        let mut state = self.state.lock().unwrap();
+
+        let elapsed = now.saturating_duration_since(state.updated).as_secs_f64();
+        state.tokens =
+            (state.tokens + elapsed * self.limit.messages_per_sec).min(self.limit.burst as f64);
+        state.updated = state.updated.max(now);
+
+        if state.tokens >= 1.0 {
+            state.tokens -= 1.0;
+            return Ok(());
+        }
+
+        Err(Duration::from_secs_f64(
+            (1.0 - state.tokens) / self.limit.messages_per_sec,
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct YbThrottleMetrics {
+    pub delayed_orders: u64,
+    pub delayed_market_data_requests: u64,
+    pub total_delay_micros: u64,
+}
+
+/// Keeps outbound application messages within the rate limits of the gateway.
+/// Orders and market data requests have separate buckets, Logon and Heartbeat are never delayed.
+/// YbTcpSate holds one built from the settings and applies it in `send` only.
+/// Messages written with `connection.send` bypass it: the serializer is sync and can't wait
+pub struct YbOutboundThrottle {
+    orders: Option<YbTokenBucket>,
+    market_data: Option<YbTokenBucket>,
+    delayed_orders: AtomicU64,
+    delayed_market_data_requests: AtomicU64,
+    total_delay_micros: AtomicU64,
+}
+
+impl YbOutboundThrottle {
+    pub fn new(orders: Option<YbRateLimit>, market_data: Option<YbRateLimit>) -> Self {
+        Self {
+            orders: orders.map(YbTokenBucket::new),
+            market_data: market_data.map(YbTokenBucket::new),
+            delayed_orders: AtomicU64::new(0),
+            delayed_market_data_requests: AtomicU64::new(0),
+            total_delay_micros: AtomicU64::new(0),
+        }
+    }
+
+    pub fn from_settings(settings: &YbFixSettings) -> Self {
+        Self::new(settings.order_rate_limit, settings.market_data_rate_limit)
+    }
+
+    /// Waits until the message can be sent without breaking the rate limit
+    pub async fn acquire(&self, message: &YbOutgoingMessage) {
+        let (bucket, delayed_counter) = match message {
+            YbOutgoingMessage::PlaceOrder(_) | YbOutgoingMessage::OrderMassStatusRequest(_) => {
+                (self.orders.as_ref(), &self.delayed_orders)
+            }
+            YbOutgoingMessage::InstrumentSubscribe(_)
+            | YbOutgoingMessage::InstrumentUnsubscribe(_) => (
+                self.market_data.as_ref(),
+                &self.delayed_market_data_requests,
+            ),
+            YbOutgoingMessage::Logon | YbOutgoingMessage::Ping => return,
+        };
+
+        let bucket = match bucket {
+            Some(bucket) => bucket,
+            None => return,
+        };
+
+        let started = Instant::now();
+        let mut delayed = false;
+
+        while let Err(wait) = bucket.try_acquire_at(Instant::now()) {
+            delayed = true;
+            tokio::time::sleep(wait).await;
+        }
+
+        if delayed {
+            delayed_counter.fetch_add(1, Ordering::Relaxed);
+            self.total_delay_micros
+                .fetch_add(started.elapsed().as_micros() as u64, Ordering::Relaxed);
+        }
+    }
+
+    pub fn get_metrics(&self) -> YbThrottleMetrics {
+        YbThrottleMetrics {
+            delayed_orders: self.delayed_orders.load(Ordering::Relaxed),
+            delayed_market_data_requests: self.delayed_market_data_requests.load(Ordering::Relaxed),
+            total_delay_micros: self.total_delay_micros.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tcp_messages::OrderMassStatusRequestYbTcpContract;
+
+    #[test]
+    fn test_token_bucket() {
+        let bucket = YbTokenBucket::new(YbRateLimit {
+            messages_per_sec: 10.0,
+            burst: 2,
+        });
+        let now = Instant::now();
+
+        assert!(bucket.try_acquire_at(now).is_ok());
+        assert!(bucket.try_acquire_at(now).is_ok());
+
+        let wait = bucket.try_acquire_at(now).unwrap_err();
+        assert_eq!(wait.as_millis(), 100);
+
+        assert!(bucket.try_acquire_at(now + wait).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_counts_delayed_messages_only() {
+        let limit = YbRateLimit {
+            messages_per_sec: 1000.0,
+            burst: 1,
+        };
+        let throttle = YbOutboundThrottle::new(Some(limit), None);
+
+        for _ in 0..3 {
+            throttle.acquire(&YbOutgoingMessage::Ping).await;
+        }
+
+        for id in ["MS-1", "MS-2"] {
+            let contract = OrderMassStatusRequestYbTcpContract::new(id);
+            throttle
+                .acquire(&YbOutgoingMessage::OrderMassStatusRequest(contract))
+                .await;
+        }
+
+        let metrics = throttle.get_metrics();
+        assert_eq!(metrics.delayed_orders, 1);
+        assert_eq!(metrics.delayed_market_data_requests, 0);
+    }
+}
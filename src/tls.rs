@@ -0,0 +1,331 @@
+use std::{
+    fs::File,
+    io::BufReader,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use my_tcp_sockets::TcpClientSocketSettings;
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::Semaphore,
+    task::JoinHandle,
+};
+use tokio_rustls::{
+    rustls::{
+        pki_types::{CertificateDer, PrivateKeyDer, ServerName},
+        ClientConfig, RootCertStore,
+    },
+    TlsConnector,
+};
+
+use crate::{logging::YbFixLogContext, settings::parse_host_port, YbFixTlsSettings};
+
+#[derive(Debug)]
+pub enum YbTlsError {
+    Io(String),
+    InvalidCertificate(String),
+    InvalidServerName(String),
+}
+
+impl std::fmt::Display for YbTlsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "TLS io error: {}", err),
+            Self::InvalidCertificate(err) => write!(f, "Invalid TLS certificate: {}", err),
+            Self::InvalidServerName(name) => write!(f, "Invalid TLS server name '{}'", name),
+        }
+    }
+}
+
+impl std::error::Error for YbTlsError {}
+
+/// Local TLS terminator in place of a stunnel sidecar.
+/// Listens on 127.0.0.1 at a random port and forwards the accepted connection over TLS
+/// to the gateway returned by `upstream`. Give the tunnel to the TcpClient as its settings:
+/// `get_host_port` waits for `upstream` and only then lets one connection through.
+///
+/// The listener is plaintext and does not authenticate its clients. While the session is up
+/// other connections are closed as soon as they are accepted. During a reconnect, a local
+/// process which connects after `get_host_port` and before the TcpClient does gets the gateway
+/// session, authenticated with the client certificate, instead of it
+pub struct YbTlsTunnel {
+    local_addr: SocketAddr,
+    upstream: Arc<dyn TcpClientSocketSettings + Send + Sync + 'static>,
+    //Gateway returned by `upstream`, taken by the next accepted connection
+    next_url: Arc<Mutex<Option<String>>>,
+    accept_task: JoinHandle<()>,
+}
+
+impl YbTlsTunnel {
+    pub async fn start(
+        tls_settings: &YbFixTlsSettings,
+        upstream: Arc<dyn TcpClientSocketSettings + Send + Sync + 'static>,
+    ) -> Result<Self, YbTlsError> {
+        let connector = TlsConnector::from(Arc::new(create_client_config(tls_settings)?));
+
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|err| YbTlsError::Io(err.to_string()))?;
+
+        let local_addr = listener
+            .local_addr()
+            .map_err(|err| YbTlsError::Io(err.to_string()))?;
+
+        let server_name = tls_settings.server_name.clone();
+        let next_url = Arc::new(Mutex::new(None));
+        let forwarding = Arc::new(Semaphore::new(1));
+
+        let accept_next_url = next_url.clone();
+
+        let accept_task = tokio::spawn(async move {
+            loop {
+                let local = match listener.accept().await {
+                    Ok((local, _)) => local,
+                    Err(err) => {
+                        crate::logging::write_error(
+                            "YbTlsTunnel",
+                            format!("Can not accept local connection: {}", err),
+                            YbFixLogContext::default(),
+                        );
+                        continue;
+                    }
+                };
+
+                let permit = match forwarding.clone().try_acquire_owned() {
+                    Ok(permit) => permit,
+                    Err(_) => {
+                        crate::logging::write_warning(
+                            "YbTlsTunnel",
+                            format!(
+                                "Local connection from {:?} is closed: another one is forwarded",
+                                local.peer_addr()
+                            ),
+                            YbFixLogContext::default(),
+                        );
+                        continue;
+                    }
+                };
+
+                let url = match accept_next_url.lock().unwrap().take() {
+                    Some(url) => url,
+                    None => {
+                        crate::logging::write_warning(
+                            "YbTlsTunnel",
+                            format!(
+                                "Local connection from {:?} is closed: no gateway is requested",
+                                local.peer_addr()
+                            ),
+                            YbFixLogContext::default(),
+                        );
+                        continue;
+                    }
+                };
+
+                let connector = connector.clone();
+                let server_name = server_name.clone();
+
+                tokio::spawn(async move {
+                    let result = forward(local, connector, url, server_name).await;
+                    drop(permit);
+
+                    if let Err(err) = result {
+                        crate::logging::write_error(
+                            "YbTlsTunnel",
+                            err.to_string(),
+                            YbFixLogContext::default(),
+                        );
+                    }
+                });
+            }
+        });
+
+        Ok(Self {
+            local_addr,
+            upstream,
+            next_url,
+            accept_task,
+        })
+    }
+
+    pub fn get_local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+impl Drop for YbTlsTunnel {
+    fn drop(&mut self) {
+        self.accept_task.abort();
+    }
+}
+
+#[async_trait::async_trait]
+impl TcpClientSocketSettings for YbTlsTunnel {
+    async fn get_host_port(&self) -> String {
+        // Backoff and the wait for the session are done here, before anything is accepted
+        let url = self.upstream.get_host_port().await;
+        *self.next_url.lock().unwrap() = Some(url);
+        self.local_addr.to_string()
+    }
+}
+
+async fn forward(
+    mut local: TcpStream,
+    connector: TlsConnector,
+    url: String,
+    server_name: Option<String>,
+) -> Result<(), YbTlsError> {
+    let server_name = match server_name {
+        Some(server_name) => server_name,
+        None => match parse_host_port(&url) {
+            Ok((host, _)) => host.to_string(),
+            Err(_) => return Err(YbTlsError::InvalidServerName(url)),
+        },
+    };
+
+    let server_name = ServerName::try_from(server_name.clone())
+        .map_err(|_| YbTlsError::InvalidServerName(server_name))?;
+
+    let remote = TcpStream::connect(url.as_str())
+        .await
+        .map_err(|err| YbTlsError::Io(format!("{}: {}", url, err)))?;
+
+    let remote = connector
+        .connect(server_name, remote)
+        .await
+        .map_err(|err| YbTlsError::Io(format!("{}: handshake failed: {}", url, err)))?;
+
+    let (mut local_read, mut local_write) = local.split();
+    let (mut remote_read, mut remote_write) = tokio::io::split(remote);
+
+    // Ends as soon as either side is closed. The other one is dropped with it
+    let result = tokio::select! {
+        result = tokio::io::copy(&mut local_read, &mut remote_write) => result,
+        result = tokio::io::copy(&mut remote_read, &mut local_write) => result,
+    };
+
+    result.map_err(|err| YbTlsError::Io(format!("{}: {}", url, err)))?;
+
+    Ok(())
+}
+
+fn create_client_config(tls_settings: &YbFixTlsSettings) -> Result<ClientConfig, YbTlsError> {
+    let mut roots = RootCertStore::empty();
+
+    match tls_settings.ca_bundle_path.as_ref() {
+        Some(path) => {
+            for cert in read_certs(path)? {
+                roots
+                    .add(cert)
+                    .map_err(|err| YbTlsError::InvalidCertificate(format!("{}: {}", path, err)))?;
+            }
+        }
+        None => roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+    }
+
+    let builder = ClientConfig::builder().with_root_certificates(roots);
+
+    let (cert_path, key_path) = match (
+        tls_settings.client_cert_path.as_ref(),
+        tls_settings.client_key_path.as_ref(),
+    ) {
+        (Some(cert_path), Some(key_path)) => (cert_path, key_path),
+        _ => return Ok(builder.with_no_client_auth()),
+    };
+
+    builder
+        .with_client_auth_cert(read_certs(cert_path)?, read_private_key(key_path)?)
+        .map_err(|err| YbTlsError::InvalidCertificate(format!("{}: {}", cert_path, err)))
+}
+
+fn read_certs(path: &str) -> Result<Vec<CertificateDer<'static>>, YbTlsError> {
+    let file = File::open(path).map_err(|err| YbTlsError::Io(format!("{}: {}", path, err)))?;
+
+    rustls_pemfile::certs(&mut BufReader::new(file))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| YbTlsError::InvalidCertificate(format!("{}: {}", path, err)))
+}
+
+fn read_private_key(path: &str) -> Result<PrivateKeyDer<'static>, YbTlsError> {
+    let file = File::open(path).map_err(|err| YbTlsError::Io(format!("{}: {}", path, err)))?;
+
+    match rustls_pemfile::private_key(&mut BufReader::new(file)) {
+        Ok(Some(key)) => Ok(key),
+        Ok(None) => Err(YbTlsError::InvalidCertificate(format!(
+            "{}: no private key found",
+            path
+        ))),
+        Err(err) => Err(YbTlsError::InvalidCertificate(format!("{}: {}", path, err))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_rustls::{rustls::ServerConfig, TlsAcceptor};
+
+    use super::*;
+    use crate::failover::YbEndpointSelector;
+
+    const TLS_FOLDER: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/tls");
+
+    // Echo server behind TLS with a certificate issued for localhost by testdata/tls/ca.pem
+    async fn start_tls_acceptor() -> SocketAddr {
+        let config = ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(
+                read_certs(&format!("{}/server.pem", TLS_FOLDER)).unwrap(),
+                read_private_key(&format!("{}/server.key", TLS_FOLDER)).unwrap(),
+            )
+            .unwrap();
+
+        let acceptor = TlsAcceptor::from(Arc::new(config));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = acceptor.accept(stream).await.unwrap();
+            let mut buffer = [0u8; 64];
+            let size = stream.read(&mut buffer).await.unwrap();
+            stream.write_all(&buffer[..size]).await.unwrap();
+            stream.flush().await.unwrap();
+        });
+
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_forwards_over_tls() {
+        let acceptor_addr = start_tls_acceptor().await;
+
+        let mut settings = crate::tests_support::settings();
+        settings.url = acceptor_addr.to_string();
+
+        let tls_settings = YbFixTlsSettings {
+            ca_bundle_path: Some(format!("{}/ca.pem", TLS_FOLDER)),
+            client_cert_path: None,
+            client_key_path: None,
+            server_name: Some("localhost".to_string()),
+        };
+
+        let upstream = Arc::new(YbEndpointSelector::from_settings(&settings));
+        let tunnel = YbTlsTunnel::start(&tls_settings, upstream).await.unwrap();
+
+        // Connection which is not requested with get_host_port is closed
+        let mut buffer = [0u8; 10];
+        let mut other = TcpStream::connect(tunnel.get_local_addr()).await.unwrap();
+        assert_eq!(other.read(&mut buffer).await.unwrap(), 0);
+
+        let local_addr = tunnel.get_host_port().await;
+        let mut local = TcpStream::connect(local_addr.as_str()).await.unwrap();
+        local.write_all(b"8=FIX.4.4\x01").await.unwrap();
+
+        local.read_exact(&mut buffer).await.unwrap();
+        assert_eq!(&buffer, b"8=FIX.4.4\x01");
+
+        // Second connection is closed while the first one is forwarded
+        let mut other = TcpStream::connect(tunnel.get_local_addr()).await.unwrap();
+        assert_eq!(other.read(&mut buffer).await.unwrap(), 0);
+    }
+}